@@ -4,4 +4,20 @@ resolution=$(xrandr | grep '*' | awk '{print $1}')
 width=$(echo $resolution | cut -d 'x' -f 1)
 height=$(echo $resolution | cut -d 'x' -f 2)
 
-kwin_wayland --xwayland --width $width --height $height --exit-with-session "konsole -e ./partydeck-rs --fullscreen"
+# Quotes an argument with POSIX single quotes, which kwin_wayland's command parsing
+# understands, unlike the $'...' quoting printf %q can produce
+quote() {
+    local escaped="'\\''"
+    printf "'%s' " "${1//\'/$escaped}"
+}
+
+# Arguments are passed on to partydeck-rs, e.g. from a shortcut made for a preset
+args="--fullscreen"
+if [ $# -gt 0 ]; then
+    args=""
+    for arg in "$@"; do
+        args+=$(quote "$arg")
+    done
+fi
+
+kwin_wayland --xwayland --width $width --height $height --exit-with-session "konsole -e ./partydeck-rs $args"
@@ -21,6 +21,13 @@ Ypos_4p = [0, 0, (scrheight / 2), (scrheight / 2)]
 Xsize_4p = [(scrwidth / 2), (scrwidth / 2), (scrwidth / 2), (scrwidth / 2)]
 Ysize_4p = [(scrheight / 2), (scrheight / 2), (scrheight / 2), (scrheight / 2)]
 
+function slotOf(client){
+    if (client.pid in slotByPid){
+        return slotByPid[client.pid];
+    }
+    return 100;
+}
+
 function gamescopeSplitscreen(){
     var allClients = workspace.windowList();
     var gamescopeClients = []
@@ -30,7 +37,12 @@ function gamescopeSplitscreen(){
             gamescopeClients.push(allClients[i]);
         }
     }
-    switch (gamescopeClients.length){
+    // A session script defines slotByPid and playerCount so every instance keeps its tile,
+    // even when another instance is gone or has been relaunched
+    var hasSlots = (typeof slotByPid !== 'undefined');
+    var count = hasSlots ? playerCount : gamescopeClients.length;
+
+    switch (count){
         case 0:
             return;
         case 1:
@@ -59,11 +71,15 @@ function gamescopeSplitscreen(){
             break;
     }
 
-    for (var i = 0; i < gamescopeClients.length; i++){
-        gamescopeClients[i].noBorder = true;
-        gamescopeClients[i].keepAbove = true;
-        gamescopeClients[i].skipTaskbar = true;
-        gamescopeClients[i].frameGeometry = {
+    for (var j = 0; j < gamescopeClients.length; j++){
+        var i = hasSlots ? slotOf(gamescopeClients[j]) : j;
+        if (i >= count){
+            continue;
+        }
+        gamescopeClients[j].noBorder = true;
+        gamescopeClients[j].keepAbove = true;
+        gamescopeClients[j].skipTaskbar = true;
+        gamescopeClients[j].frameGeometry = {
             x: Xpos[i],
             y: Ypos[i],
             width: Xsize[i],
@@ -74,3 +90,4 @@ function gamescopeSplitscreen(){
 
 workspace.windowAdded.connect(gamescopeSplitscreen);
 workspace.windowRemoved.connect(gamescopeSplitscreen);
+gamescopeSplitscreen();
@@ -1,9 +1,15 @@
 use crate::app::config::*;
 use crate::game::{Game::*, *};
 use crate::handler::*;
+use crate::hotplug::{HotplugEvent, InputWatcher};
 use crate::input::*;
-use crate::launch::{launch_executable, launch_from_handler};
+use crate::launch::{
+    CpuPinning, LaunchPlan, Runner, UpscaleFilter, WRAPPER_SUGGESTIONS, launch_executable,
+    launch_from_handler, resolve_profiles, shell_quote,
+};
 use crate::paths::*;
+use crate::remap::{PadMapping, VirtualButton};
+use crate::session::*;
 use crate::task::Task;
 use crate::util::*;
 
@@ -11,18 +17,54 @@ use dialog::DialogBox;
 use eframe::egui::{self, Color32, Key, RichText, TextStyle, Ui};
 use egui_commonmark::{CommonMarkCache, CommonMarkViewer};
 use regex::Regex;
+use rfd::FileDialog;
 use std::path::PathBuf;
 
-#[derive(Eq, PartialEq)]
+#[derive(Eq, PartialEq, Clone, Copy)]
 pub enum MenuPage {
     Games,
     Settings,
     Profiles,
     Game,
     Players,
+    Session,
     About,
+    Remap,
 }
 
+// Shows the logs of a game's last session on the game page
+#[derive(Default)]
+pub struct LogViewer {
+    pub uid: String,
+    pub files: Vec<PathBuf>,
+    pub selected: usize,
+    pub text: String,
+}
+
+// Whose remaps and stick settings are being edited on the remap page
+#[derive(Clone, PartialEq)]
+pub enum MappingTarget {
+    Profile(String),
+    // Gamepad::model
+    Model(String),
+}
+
+// Remap page state: the mapping being edited, the row selected with a pad, and the pad shown
+#[derive(Default)]
+pub struct RemapEditor {
+    pub target: Option<MappingTarget>,
+    pub row: usize,
+    pub pad: usize,
+}
+
+// One row per button, four per stick, then "Swap A/B and X/Y" and "Reset"
+const REMAP_ROWS: usize = 21;
+const REMAP_STICK_ROWS: usize = 11;
+const REMAP_SWAP_ROW: usize = 19;
+const REMAP_RESET_ROW: usize = 20;
+// Points scrolled per frame with a trigger fully pressed
+const NAV_SCROLL_SPEED: f32 = 30.0;
+
 pub struct PartyApp {
     pub needs_update: bool,
     pub update_check: Option<Task<bool>>,
@@ -30,12 +72,26 @@ pub struct PartyApp {
     pub cur_page: MenuPage,
     pub infotext: String,
     pub pads: Vec<Gamepad>,
+    pub kbm: Vec<KbmDevice>,
+    pub input_watcher: Option<InputWatcher>,
+    // Shown on the Players page when a player's device was unplugged
+    pub input_notice: Option<String>,
     pub players: Vec<Player>,
     pub games: Vec<Game>,
     pub game_scan: Option<Task<Vec<Game>>>,
     pub profiles: Vec<String>,
     pub selected_game: usize,
     pub md_cache: CommonMarkCache,
+    pub session: Option<Session>,
+    pub log_viewer: LogViewer,
+    pub prefixes: Vec<PrefixInfo>,
+    pub prefix_scan: Option<Task<Vec<PrefixInfo>>>,
+    pub proton_builds: Vec<ProtonBuild>,
+    pub audio_sinks: Vec<AudioSink>,
+    pub preset_draft: SessionPreset,
+    pub remap: RemapEditor,
+    // Scrolling requested with the triggers, applied to the page on the next frame
+    pub nav_scroll: f32,
 }
 
 macro_rules! cur_game {
@@ -53,6 +109,16 @@ impl Default for PartyApp {
                 check_for_partydeck_update().unwrap_or(false)
             })),
             pads: scan_evdev_gamepads(options.disable_steam_input),
+            kbm: scan_evdev_kbm(),
+            input_watcher: match InputWatcher::start() {
+                Ok(watcher) => Some(watcher),
+                Err(err) => {
+                    log_error(&format!("Device hotplug unavailable: {err}"));
+                    None
+                }
+            },
+            input_notice: None,
+            proton_builds: scan_proton_builds(&options.proton_extra_dirs),
             options,
             cur_page: MenuPage::Games,
             infotext: String::new(),
@@ -62,14 +128,30 @@ impl Default for PartyApp {
             profiles: Vec::new(),
             selected_game: 0,
             md_cache: CommonMarkCache::default(),
+            session: None,
+            log_viewer: LogViewer::default(),
+            prefixes: Vec::new(),
+            prefix_scan: None,
+            audio_sinks: Vec::new(),
+            preset_draft: SessionPreset::default(),
+            remap: RemapEditor::default(),
+            nav_scroll: 0.0,
         }
     }
 }
 
 impl eframe::App for PartyApp {
     fn raw_input_hook(&mut self, _ctx: &egui::Context, raw_input: &mut egui::RawInput) {
+        self.handle_hotplug();
         match self.cur_page {
             MenuPage::Players => self.handle_gamepad_players(),
+            MenuPage::Remap => self.handle_gamepad_remap(),
+            // Pads belong to the game instances during a session, discard their input
+            MenuPage::Session => {
+                for pad in &mut self.pads {
+                    let _ = pad.poll();
+                }
+            }
             _ => self.handle_gamepad_gui(raw_input),
         }
     }
@@ -90,6 +172,26 @@ impl eframe::App for PartyApp {
                 self.game_scan = None;
             }
         }
+        if let Some(task) = &self.prefix_scan {
+            if let Some(prefixes) = task.try_join() {
+                self.prefixes = prefixes;
+                self.prefix_scan = None;
+            }
+        }
+        if let Some(session) = &mut self.session {
+            if session.take_finished() {
+                log_info("Session finished");
+                // Only this session's guests, a CLI session may be using others
+                for instance in &session.status().instances {
+                    if let Err(err) = remove_guest_profile(&instance.profile) {
+                        log_error(&format!(
+                            "Couldn't remove guest profile {}: {err}",
+                            instance.profile
+                        ));
+                    }
+                }
+            }
+        }
         let side_w = 200.0;
         egui::SidePanel::left("left_panel")
             .resizable(false)
@@ -117,29 +219,43 @@ impl eframe::App for PartyApp {
 
         if (self.cur_page != MenuPage::Games)
             && (self.cur_page != MenuPage::Players)
+            && (self.cur_page != MenuPage::Session)
             && (self.cur_page != MenuPage::About)
         {
             self.display_info_panel(ctx);
         }
 
-        egui::CentralPanel::default().show(ctx, |ui| match self.cur_page {
-            MenuPage::Games => {
-                self.display_page_games(ui);
+        egui::CentralPanel::default().show(ctx, |ui| {
+            // Taken by the first scroll area on the page
+            if self.nav_scroll != 0.0 {
+                ui.scroll_with_delta(egui::vec2(0.0, self.nav_scroll));
+                self.nav_scroll = 0.0;
             }
-            MenuPage::Settings => {
-                self.display_page_settings(ui);
-            }
-            MenuPage::Profiles => {
-                self.display_page_profiles(ui);
-            }
-            MenuPage::Game => {
-                self.display_page_game(ui);
-            }
-            MenuPage::Players => {
-                self.display_page_players(ui);
-            }
-            MenuPage::About => {
-                self.display_page_about(ui);
+            match self.cur_page {
+                MenuPage::Games => {
+                    self.display_page_games(ui);
+                }
+                MenuPage::Settings => {
+                    self.display_page_settings(ui);
+                }
+                MenuPage::Profiles => {
+                    self.display_page_profiles(ui);
+                }
+                MenuPage::Game => {
+                    self.display_page_game(ui);
+                }
+                MenuPage::Players => {
+                    self.display_page_players(ui);
+                }
+                MenuPage::Session => {
+                    self.display_page_session(ui);
+                }
+                MenuPage::About => {
+                    self.display_page_about(ui);
+                }
+                MenuPage::Remap => {
+                    self.display_page_remap(ui);
+                }
             }
         });
 
@@ -184,6 +300,11 @@ impl PartyApp {
                             PadType::Unknown => egui::include_image!("../../res/gamepad.svg"),
                         };
                         ui.add(egui::Image::new(image).max_height(20.0));
+                        if let Some(binding) = self.options.pad_bindings.get(&pad.identity())
+                            && !binding.nickname.is_empty()
+                        {
+                            ui.label(&binding.nickname);
+                        }
                         if let Some(id) = pad.event_id() {
                             ui.label(format!("({})", id));
                         }
@@ -203,9 +324,7 @@ impl PartyApp {
                 ))
                 .clicked()
             {
-                self.players.clear();
-                self.pads.clear();
-                self.pads = scan_evdev_gamepads(self.options.disable_steam_input);
+                self.rescan_devices();
             }
             if self.update_check.is_some() {
                 ui.label("Checking for updates...");
@@ -217,12 +336,7 @@ impl PartyApp {
         ui.style_mut().spacing.item_spacing.x = 20.0;
         ui.with_layout(egui::Layout::left_to_right(egui::Align::Center), |ui| {
             ui.add_space(20.0);
-            let pages = [
-                (MenuPage::Games, "GAMES"),
-                (MenuPage::Profiles, "PROFILES"),
-                (MenuPage::Settings, "SETTINGS"),
-            ];
-            for (page, label) in pages {
+            for (page, label) in self.nav_pages() {
                 let resp = ui.add(
                     egui::Label::new(
                         RichText::new(label).text_style(egui::TextStyle::Name("Nav".into())),
@@ -282,6 +396,32 @@ impl PartyApp {
         });
     }
 
+    fn nav_pages(&self) -> Vec<(MenuPage, &'static str)> {
+        let mut pages = vec![
+            (MenuPage::Games, "GAMES"),
+            (MenuPage::Profiles, "PROFILES"),
+            (MenuPage::Settings, "SETTINGS"),
+        ];
+        if self.session.is_some() {
+            pages.push((MenuPage::Session, "SESSION"));
+        }
+        pages
+    }
+
+    // Steps through the pages of the nav bar, for LB and RB
+    fn switch_nav_page(&mut self, step: i32) {
+        let pages = self.nav_pages();
+        let next = match pages.iter().position(|(page, _)| *page == self.cur_page) {
+            Some(i) => (i as i32 + step).rem_euclid(pages.len() as i32) as usize,
+            None => 0,
+        };
+        let page = pages[next].0;
+        if page == MenuPage::Profiles {
+            self.profiles = scan_profiles(false);
+        }
+        self.cur_page = page;
+    }
+
     fn display_info_panel(&mut self, ctx: &egui::Context) {
         egui::TopBottomPanel::bottom("info_panel")
             .exact_height(33.0)
@@ -300,6 +440,9 @@ impl PartyApp {
                     MenuPage::Profiles => {
                         self.infotext = "Create profiles to persistently store game save data, settings, and stats.".to_string();
                     }
+                    MenuPage::Remap => {
+                        self.infotext = "Remaps and stick settings are applied by a virtual controller standing in for the player's own, which needs write access to /dev/uinput. A profile's mapping is used over its controller model's.".to_string();
+                    }
                     _ => {}
                 }
                 egui::ScrollArea::vertical().show(ui, |ui| {
@@ -453,6 +596,18 @@ impl PartyApp {
             &mut self.options.vertical_two_player,
             "Vertical split for 2 players",
         );
+        let network_isolation_check = ui.checkbox(
+            &mut self.options.network_isolation,
+            "Separate network per instance (virtual LAN)",
+        );
+        let virtual_pads_check = ui.checkbox(
+            &mut self.options.virtual_pads,
+            "Virtual controllers",
+        );
+        let session_logs_slider = ui.add(
+            egui::Slider::new(&mut self.options.session_logs_kept, 1..=50)
+                .text("Session logs to keep"),
+        );
 
         if force_sdl2_check.hovered() {
             self.infotext = "Forces games to use the version of SDL2 included in the Steam Runtime. Only works on native Linux games, may fix problematic game controller support (incorrect mappings) in some games, may break others. If unsure, leave this unchecked.".to_string();
@@ -466,35 +621,148 @@ impl PartyApp {
         if disable_steam_input_check.hovered() {
             self.infotext = "Ignore Steam Input virtual devices to avoid duplicate controllers.".to_string();
         }
+        if session_logs_slider.hovered() {
+            self.infotext = "Every session writes its launch plan and the output of each instance to a folder in the PartyDeck data folder under logs. Older session folders are deleted automatically.".to_string();
+        }
+        if network_isolation_check.hovered() {
+            self.infotext = "Gives every instance its own network namespace with its own IP address on a private virtual LAN, so games that bind a fixed port or refuse to run twice on one machine can find each other like separate PCs. Internet access requires slirp4netns and nftables. Requires unshare, nsenter and ip. If unsure, leave this unchecked.".to_string();
+        }
+        if virtual_pads_check.hovered() {
+            self.infotext = "Gives every player a virtual Xbox 360 controller fed by their own controller, instead of passing the controller itself to the game. Helps games that only accept XInput controllers, and lets a player reconnect their controller mid-session. Requires write access to /dev/uinput.".to_string();
+        }
         if vertical_two_player_check.hovered() {
             self.infotext = "Toggle how two player sessions are arranged. Enabled = vertical split (stacked). Disabled = horizontal split (side by side).".to_string();
         }
 
-        ui.horizontal(|ui| {
-        let proton_ver_label = ui.label("Proton version");
-        let proton_ver_editbox = ui.add(
-            egui::TextEdit::singleline(&mut self.options.proton_version)
-                .hint_text("GE-Proton"),
-        );
-        if proton_ver_label.hovered() || proton_ver_editbox.hovered() {
-            self.infotext = "Specify a Proton version. This can be a path, e.g. \"/path/to/proton\" or just a name, e.g. \"GE-Proton\" for the latest version of Proton-GE. If left blank, this will default to \"GE-Proton\". If unsure, leave this blank.".to_string();
+        egui::CollapsingHeader::new("Gamescope")
+            .id_salt("gamescope_options")
+            .show(ui, |ui| {
+                self.display_gamescope_options(ui);
+            });
+
+        let wrappers_header = egui::CollapsingHeader::new("Wrappers")
+            .id_salt("wrappers")
+            .show(ui, |ui| {
+                ui.label("All games");
+                wrapper_list_edit(ui, "wrappers_all", &mut self.options.wrappers);
+                for (i, wrappers) in self.options.slot_wrappers.iter_mut().enumerate() {
+                    ui.separator();
+                    ui.label(format!("Player {}", i + 1));
+                    wrapper_list_edit(ui, &format!("wrappers_slot{i}"), wrappers);
+                }
+            });
+        if wrappers_header.header_response.hovered() {
+            self.infotext = "Commands each game is run through, such as gamemoderun or mangohud. Wrappers for all games come first, then the game's own (set on its page), then the player's. Each wrapper must be installed, or the launch is cancelled.".to_string();
         }
-        });
+
+        let controllers_header = egui::CollapsingHeader::new("Controllers")
+            .id_salt("pad_bindings")
+            .show(ui, |ui| {
+                self.display_pad_bindings(ui);
+                if ui.button("Remap buttons and sticks").clicked() {
+                    self.profiles = scan_profiles(false);
+                    self.cur_page = MenuPage::Remap;
+                }
+            });
+        if controllers_header.header_response.hovered() {
+            self.infotext = "Controllers are recognised by their model and serial number or Bluetooth address, or the USB port they're plugged into if they don't have one. PartyDeck remembers the profile and player slot each controller last played with, and picks them again when it joins. Give controllers a name to tell identical models apart.".to_string();
+        }
+
+        egui::CollapsingHeader::new("Performance")
+            .id_salt("resource_options")
+            .show(ui, |ui| {
+                self.display_resource_options(ui);
+            });
 
         ui.horizontal(|ui| {
-        if ui.button("Erase Proton Prefix").clicked() {
-            if yesno("Erase Prefix?", "This will erase the Wine prefix used by PartyDeck. This shouldn't erase profile/game-specific data, but exercise caution. Are you sure?") && PATH_PARTY.join("gamesyms").exists() {
-                if let Err(err) = std::fs::remove_dir_all(PATH_PARTY.join("pfx")) {
-                    msg("Error", &format!("Couldn't erase pfx data: {}", err));
+            let proton_ver_label = ui.label("Proton version");
+            let selected_text = match find_proton_build(&self.proton_builds, &self.options.proton_version) {
+                Some(build) => build.display(),
+                None if self.options.proton_version.is_empty() => "Automatic".to_string(),
+                None => self.options.proton_version.clone(),
+            };
+            let proton_ver_combo = egui::ComboBox::from_id_salt("proton_version")
+                .selected_text(selected_text)
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(
+                        &mut self.options.proton_version,
+                        String::new(),
+                        "Automatic",
+                    );
+                    for build in &self.proton_builds {
+                        ui.selectable_value(
+                            &mut self.options.proton_version,
+                            build.path.to_string_lossy().to_string(),
+                            build.display(),
+                        )
+                        .on_hover_text(build.path.display().to_string());
+                    }
+                });
+            let proton_ver_editbox = ui.add(
+                egui::TextEdit::singleline(&mut self.options.proton_version)
+                    .hint_text("GE-Proton"),
+            );
+            if ui.button("Rescan").clicked() {
+                self.proton_builds = scan_proton_builds(&self.options.proton_extra_dirs);
+            }
+            if proton_ver_label.hovered() || proton_ver_combo.response.hovered() || proton_ver_editbox.hovered() {
+                self.infotext = "Pick an installed Proton build, or type a path, e.g. \"/path/to/proton\" or a name, e.g. \"GE-Proton9-20\". Automatic uses the newest installed GE-Proton, or lets UMU download the latest one if none is installed. Builds are found in Steam's compatibilitytools.d and steamapps/common folders, plus any extra folders below. If unsure, leave this on Automatic.".to_string();
+            }
+        });
+
+        egui::CollapsingHeader::new("Extra Proton folders")
+            .id_salt("proton_extra_dirs")
+            .show(ui, |ui| {
+                let mut remove = None;
+                for (i, dir) in self.options.proton_extra_dirs.iter().enumerate() {
+                    ui.horizontal(|ui| {
+                        ui.label(dir);
+                        if ui.button("Remove").clicked() {
+                            remove = Some(i);
+                        }
+                    });
                 }
-                else if let Err(err) = std::fs::create_dir_all(PATH_PARTY.join("pfx")) {
-                    msg("Error", &format!("Couldn't re-create pfx directory: {}", err));
+                if let Some(i) = remove {
+                    self.options.proton_extra_dirs.remove(i);
+                    self.proton_builds = scan_proton_builds(&self.options.proton_extra_dirs);
                 }
-                else {
-                    msg("Data Erased", "Proton prefix data successfully erased.");
+                if ui.button("Add folder").clicked()
+                    && let Some(dir) = FileDialog::new().set_title("Select Proton folder").pick_folder()
+                {
+                    self.options.proton_extra_dirs.push(dir.to_string_lossy().to_string());
+                    self.proton_builds = scan_proton_builds(&self.options.proton_extra_dirs);
                 }
+            });
+
+        ui.horizontal(|ui| {
+            let prefix_label = ui.label("Wine prefixes");
+            let prefix_combo = egui::ComboBox::from_id_salt("prefix_strategy")
+                .selected_text(self.options.prefix_strategy.label())
+                .show_ui(ui, |ui| {
+                    for strategy in [
+                        PrefixStrategy::Shared,
+                        PrefixStrategy::PerSlot,
+                        PrefixStrategy::PerProfile,
+                    ] {
+                        ui.selectable_value(
+                            &mut self.options.prefix_strategy,
+                            strategy,
+                            strategy.label(),
+                        );
+                    }
+                });
+            if prefix_label.hovered() || prefix_combo.response.hovered() {
+                self.infotext = "Choose which Wine prefix each Proton instance uses. Shared: all instances use one prefix. Per player slot / Per profile: every instance gets its own prefix for each runner and Proton build, cloned the first time from that build's template prefix (custom commands create their own), so registry settings and shader caches don't leak between players. Guests use their player slot's prefix.".to_string();
             }
-        }
+        });
+
+        egui::CollapsingHeader::new("Manage Wine prefixes")
+            .id_salt("manage_prefixes")
+            .show(ui, |ui| {
+                self.display_prefix_manager(ui);
+            });
+
+        ui.horizontal(|ui| {
         if ui.button("Erase Symlink Data").clicked() {
             if yesno("Erase Symlink Data?", "This will erase all game symlink data. This shouldn't erase profile/game-specific data, but exercise caution. Are you sure?") && PATH_PARTY.join("gamesyms").exists() {
                 if let Err(err) = std::fs::remove_dir_all(PATH_PARTY.join("gamesyms")) {
@@ -547,6 +815,235 @@ impl PartyApp {
             });
     }
 
+    fn display_pad_bindings(&mut self, ui: &mut Ui) {
+        for pad in &self.pads {
+            let identity = pad.identity();
+            let mut nickname = self
+                .options
+                .pad_bindings
+                .get(&identity)
+                .map(|b| b.nickname.clone())
+                .unwrap_or_default();
+            ui.horizontal(|ui| {
+                ui.label(format!(
+                    "{} ({})",
+                    pad.fancyname(),
+                    pad.event_id().unwrap_or_default()
+                ));
+                if ui
+                    .add(egui::TextEdit::singleline(&mut nickname).hint_text("Name"))
+                    .changed()
+                {
+                    self.options
+                        .pad_bindings
+                        .entry(identity)
+                        .or_default()
+                        .nickname = nickname;
+                }
+            });
+        }
+
+        let mut forget = None;
+        egui::Grid::new("pad_bindings_grid")
+            .num_columns(4)
+            .spacing([20.0, 4.0])
+            .show(ui, |ui| {
+                for (identity, binding) in &self.options.pad_bindings {
+                    match binding.nickname.is_empty() {
+                        true => ui.label(identity),
+                        false => ui.label(&binding.nickname).on_hover_text(identity),
+                    };
+                    ui.label(match binding.profile.is_empty() {
+                        true => "Guest",
+                        false => &binding.profile,
+                    });
+                    ui.label(match binding.slot {
+                        Some(slot) => format!("Player {}", slot + 1),
+                        None => String::new(),
+                    });
+                    if ui.button("Forget").clicked() {
+                        forget = Some(identity.clone());
+                    }
+                    ui.end_row();
+                }
+            });
+        if let Some(identity) = forget {
+            self.options.pad_bindings.remove(&identity);
+        }
+    }
+
+    fn display_gamescope_options(&mut self, ui: &mut Ui) {
+        let opts = &mut self.options.gamescope;
+        let internal_scale_slider = ui.add(
+            egui::Slider::new(&mut opts.internal_scale, 25..=100)
+                .text("Internal resolution")
+                .suffix("%"),
+        );
+        let filter_combo = ui
+            .add_enabled_ui(opts.internal_scale < 100, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Upscaling filter");
+                    egui::ComboBox::from_id_salt("gamescope_filter")
+                        .selected_text(opts.filter.label())
+                        .show_ui(ui, |ui| {
+                            for filter in UpscaleFilter::ALL {
+                                ui.selectable_value(&mut opts.filter, filter, filter.label());
+                            }
+                        });
+                })
+                .response
+            })
+            .inner;
+        let sharpness_slider = ui.add_enabled(
+            opts.internal_scale < 100 && opts.filter.sharpens(),
+            egui::Slider::new(&mut opts.sharpness, 0..=20).text("Sharpness"),
+        );
+        let fps_limit_slider = ui.add(
+            egui::Slider::new(&mut opts.fps_limit, 0..=144)
+                .text("FPS limit")
+                .custom_formatter(|v, _| match v as u32 {
+                    0 => "Off".to_string(),
+                    v => v.to_string(),
+                }),
+        );
+        let hdr_check = ui.checkbox(&mut opts.hdr, "HDR");
+        let mangoapp_check = ui.checkbox(&mut opts.mangoapp, "MangoHud overlay (mangoapp)");
+        let extra_args_edit = ui
+            .horizontal(|ui| {
+                ui.label("Extra flags");
+                ui.add(
+                    egui::TextEdit::singleline(&mut opts.extra_args)
+                        .hint_text("--force-grab-cursor"),
+                )
+            })
+            .inner;
+
+        if internal_scale_slider.hovered() {
+            self.infotext = "Resolution each game renders at, relative to its gamescope window. Below 100%, gamescope upscales the game to fill the window, which improves performance.".to_string();
+        }
+        if filter_combo.hovered() || sharpness_slider.hovered() {
+            self.infotext = "How gamescope upscales games rendering below 100%. FSR and NIS sharpen the image, with sharpness ranging from 0 (sharpest) to 20 (softest).".to_string();
+        }
+        if fps_limit_slider.hovered() {
+            self.infotext = "Limits the framerate of every instance. Useful when several instances share a weak GPU like the Steam Deck's.".to_string();
+        }
+        if hdr_check.hovered() {
+            self.infotext =
+                "Enables HDR output in gamescope. Requires an HDR capable display and game."
+                    .to_string();
+        }
+        if mangoapp_check.hovered() {
+            self.infotext = "Shows a MangoHud performance overlay in each instance. Requires MangoHud to be installed.".to_string();
+        }
+        if extra_args_edit.hovered() {
+            self.infotext = "Additional gamescope flags, separated by spaces. Flags your gamescope version doesn't support are left out with a warning. Handlers can override any of these settings.".to_string();
+        }
+    }
+
+    fn display_resource_options(&mut self, ui: &mut Ui) {
+        let opts = &mut self.options.resources;
+        let pinning_combo = ui
+            .horizontal(|ui| {
+                ui.label("CPU pinning");
+                egui::ComboBox::from_id_salt("cpu_pinning")
+                    .selected_text(opts.cpu_pinning.label())
+                    .show_ui(ui, |ui| {
+                        for pinning in [CpuPinning::Off, CpuPinning::Even, CpuPinning::Custom] {
+                            ui.selectable_value(&mut opts.cpu_pinning, pinning, pinning.label());
+                        }
+                    })
+                    .response
+            })
+            .inner;
+        if opts.cpu_pinning == CpuPinning::Custom {
+            for (i, cpus) in opts.cpu_map.iter_mut().enumerate() {
+                ui.horizontal(|ui| {
+                    ui.label(format!("Player {} CPUs", i + 1));
+                    ui.add(egui::TextEdit::singleline(cpus).hint_text("0-1,4"));
+                });
+            }
+        }
+        let nice_slider = ui.add(egui::Slider::new(&mut opts.nice, 0..=19).text("Nice level"));
+        let ionice_check = ui.checkbox(&mut opts.ionice, "Set I/O priority");
+        let ionice_slider = ui.add_enabled(
+            opts.ionice,
+            egui::Slider::new(&mut opts.ionice_level, 0..=7).text("I/O priority"),
+        );
+        let memory_slider = ui.add(
+            egui::Slider::new(&mut opts.memory_limit, 0..=16384)
+                .step_by(256.0)
+                .text("Memory limit per instance")
+                .custom_formatter(|v, _| match v as u32 {
+                    0 => "Off".to_string(),
+                    v => format!("{v} MiB"),
+                }),
+        );
+
+        if pinning_combo.hovered() {
+            self.infotext = "Restricts each instance to its own CPU cores so one instance can't starve the others. Split cores evenly gives every instance the same number of whole cores. Custom uses a CPU list per player, e.g. \"0-1,4\". Requires taskset.".to_string();
+        }
+        if nice_slider.hovered() {
+            self.infotext = "Lowers the CPU priority of all instances. 0 leaves it unchanged, 19 is the lowest priority.".to_string();
+        }
+        if ionice_check.hovered() || ionice_slider.hovered() {
+            self.infotext = "Sets the disk priority of all instances, from 0 (highest) to 7 (lowest). Requires ionice.".to_string();
+        }
+        if memory_slider.hovered() {
+            self.infotext = "Caps the memory each instance may use. Instances going over the limit are stopped by the system. Requires systemd.".to_string();
+        }
+    }
+
+    fn display_prefix_manager(&mut self, ui: &mut Ui) {
+        if self.prefix_scan.is_some() {
+            ui.label("Scanning prefixes...");
+            return;
+        }
+        if ui.button("Scan").clicked() {
+            self.spawn_prefix_scan();
+        }
+        let mut rescan = false;
+        egui::Grid::new("prefixes")
+            .num_columns(4)
+            .spacing([20.0, 6.0])
+            .show(ui, |ui| {
+                for prefix in &self.prefixes {
+                    ui.label(&prefix.name)
+                        .on_hover_text(prefix.path.display().to_string());
+                    ui.label(format_size(prefix.size));
+                    if ui.button("Reset").clicked()
+                        && yesno(
+                            "Reset Prefix?",
+                            &format!("This will erase the Wine prefix \"{}\" and recreate it from the template prefix. Profile/game-specific data bound into the prefix is kept. Are you sure?", prefix.name),
+                        )
+                    {
+                        if let Err(err) = reset_prefix(&prefix.path) {
+                            msg("Error", &format!("Couldn't reset prefix: {err}"));
+                        }
+                        rescan = true;
+                    }
+                    if ui.button("Delete").clicked()
+                        && yesno(
+                            "Delete Prefix?",
+                            &format!("This will delete the Wine prefix \"{}\". It's recreated the next time it's needed. Are you sure?", prefix.name),
+                        )
+                    {
+                        if let Err(err) = delete_prefix(&prefix.path) {
+                            msg("Error", &format!("Couldn't delete prefix: {err}"));
+                        }
+                        rescan = true;
+                    }
+                    ui.end_row();
+                }
+            });
+        if rescan {
+            self.spawn_prefix_scan();
+        }
+    }
+
+    fn spawn_prefix_scan(&mut self) {
+        self.prefix_scan = Some(Task::spawn(|| scan_prefixes()));
+    }
+
     fn display_page_profiles(&mut self, ui: &mut Ui) {
         egui::Frame::new()
             .inner_margin(egui::Margin {
@@ -596,6 +1093,198 @@ impl PartyApp {
             });
     }
 
+    // Profiles and the models of connected or remapped controllers, with a label for each
+    fn mapping_targets(&self) -> Vec<(MappingTarget, String)> {
+        let mut targets: Vec<(MappingTarget, String)> = Vec::new();
+        for pad in &self.pads {
+            let target = MappingTarget::Model(pad.model());
+            if !targets.iter().any(|(t, _)| *t == target) {
+                targets.push((target, format!("{} ({})", pad.fancyname(), pad.model())));
+            }
+        }
+        for model in self.options.model_mappings.keys() {
+            let target = MappingTarget::Model(model.clone());
+            if !targets.iter().any(|(t, _)| *t == target) {
+                targets.push((target, format!("Controller {model}")));
+            }
+        }
+        for profile in &self.profiles {
+            targets.push((
+                MappingTarget::Profile(profile.clone()),
+                format!("Profile: {profile}"),
+            ));
+        }
+        targets
+    }
+
+    fn selected_mapping_target(&self, targets: &[(MappingTarget, String)]) -> usize {
+        self.remap
+            .target
+            .as_ref()
+            .and_then(|target| targets.iter().position(|(t, _)| t == target))
+            .unwrap_or(0)
+    }
+
+    fn mapping_for(&self, target: &MappingTarget) -> PadMapping {
+        let mapping = match target {
+            MappingTarget::Profile(profile) => self.options.profile_mappings.get(profile),
+            MappingTarget::Model(model) => self.options.model_mappings.get(model),
+        };
+        mapping.cloned().unwrap_or_default()
+    }
+
+    fn set_mapping(&mut self, target: &MappingTarget, mapping: PadMapping) {
+        let (mappings, key) = match target {
+            MappingTarget::Profile(profile) => (&mut self.options.profile_mappings, profile),
+            MappingTarget::Model(model) => (&mut self.options.model_mappings, model),
+        };
+        match mapping.is_default() {
+            true => mappings.remove(key),
+            false => mappings.insert(key.clone(), mapping),
+        };
+    }
+
+    fn display_page_remap(&mut self, ui: &mut Ui) {
+        let targets = self.mapping_targets();
+        egui::Frame::new()
+            .inner_margin(egui::Margin {
+                left: 20,
+                right: 20,
+                top: 20,
+                bottom: 0,
+            })
+            .show(ui, |ui| {
+                ui.heading("Controls");
+                ui.separator();
+                if targets.is_empty() {
+                    ui.label("Connect a controller or create a profile to remap its buttons.");
+                    return;
+                }
+                let selected = self.selected_mapping_target(&targets);
+                let target = targets[selected].0.clone();
+                ui.horizontal(|ui| {
+                    ui.label("Mapping for");
+                    egui::ComboBox::from_id_salt("remap_target")
+                        .selected_text(&targets[selected].1)
+                        .show_ui(ui, |ui| {
+                            for (t, label) in &targets {
+                                if ui.selectable_label(*t == target, label).clicked() {
+                                    self.remap.target = Some(t.clone());
+                                }
+                            }
+                        });
+                });
+                ui.label(
+                    RichText::new(
+                        "LB/RB: switch mapping    D-pad: choose and change    A: toggle    B: back",
+                    )
+                    .small(),
+                );
+                ui.add_space(8.0);
+
+                let mut mapping = self.mapping_for(&target);
+                let before = mapping.clone();
+                let row = self.remap.row;
+                let row_label = |ui: &mut Ui, i: usize, text: &str| match i == row {
+                    true => ui.label(RichText::new(text).color(Color32::from_rgb(0, 177, 227))),
+                    false => ui.label(text),
+                };
+                ui.horizontal_top(|ui| {
+                    ui.vertical(|ui| {
+                        egui::ScrollArea::vertical()
+                            .max_height(ui.available_height() - 40.0)
+                            .show(ui, |ui| {
+                                egui::Grid::new("remap_grid")
+                                    .num_columns(2)
+                                    .spacing([20.0, 4.0])
+                                    .show(ui, |ui| {
+                                        for (i, button) in
+                                            VirtualButton::ALL.into_iter().enumerate()
+                                        {
+                                            row_label(ui, i, button.name());
+                                            egui::ComboBox::from_id_salt(("remap_button", i))
+                                                .selected_text(mapping.button(button).name())
+                                                .show_ui(ui, |ui| {
+                                                    for to in VirtualButton::ALL {
+                                                        if ui
+                                                            .selectable_label(
+                                                                mapping.button(button) == to,
+                                                                to.name(),
+                                                            )
+                                                            .clicked()
+                                                        {
+                                                            mapping.set_button(button, to);
+                                                        }
+                                                    }
+                                                });
+                                            ui.end_row();
+                                        }
+                                        for (s, name) in
+                                            ["Left stick", "Right stick"].into_iter().enumerate()
+                                        {
+                                            let base = REMAP_STICK_ROWS + s * 4;
+                                            let shape = match s {
+                                                0 => &mut mapping.left_stick,
+                                                _ => &mut mapping.right_stick,
+                                            };
+                                            row_label(ui, base, &format!("{name} deadzone"));
+                                            ui.add(egui::Slider::new(
+                                                &mut shape.deadzone,
+                                                0.0..=0.5,
+                                            ));
+                                            ui.end_row();
+                                            row_label(ui, base + 1, &format!("{name} curve"));
+                                            ui.add(egui::Slider::new(&mut shape.curve, 0.5..=3.0));
+                                            ui.end_row();
+                                            row_label(ui, base + 2, &format!("{name} invert X"));
+                                            ui.checkbox(&mut shape.invert_x, "");
+                                            ui.end_row();
+                                            row_label(ui, base + 3, &format!("{name} invert Y"));
+                                            ui.checkbox(&mut shape.invert_y, "");
+                                            ui.end_row();
+                                        }
+                                    });
+                            });
+                        ui.horizontal(|ui| {
+                            let swap = RichText::new("Swap A/B and X/Y");
+                            let reset = RichText::new("Reset");
+                            let highlight = |text: RichText, i: usize| match i == row {
+                                true => text.color(Color32::from_rgb(0, 177, 227)),
+                                false => text,
+                            };
+                            if ui.button(highlight(swap, REMAP_SWAP_ROW)).clicked() {
+                                mapping.swap_face_buttons();
+                            }
+                            if ui.button(highlight(reset, REMAP_RESET_ROW)).clicked() {
+                                mapping = PadMapping::default();
+                            }
+                        });
+                    });
+                    ui.add_space(20.0);
+                    ui.vertical(|ui| {
+                        // Show a pad of the model being edited, otherwise the one last used on this page
+                        let pad = match &target {
+                            MappingTarget::Model(model) => {
+                                self.pads.iter().find(|pad| pad.model() == *model)
+                            }
+                            MappingTarget::Profile(_) => None,
+                        }
+                        .or_else(|| self.pads.get(self.remap.pad));
+                        match pad {
+                            Some(pad) => display_pad_visualiser(ui, pad, &mapping),
+                            None => {
+                                ui.label("Press a button on a controller to see its input here.");
+                            }
+                        }
+                    });
+                });
+
+                if mapping != before {
+                    self.set_mapping(&target, mapping);
+                }
+            });
+    }
+
     fn display_page_game(&mut self, ui: &mut Ui) {
         egui::Frame::new()
             .inner_margin(egui::Margin {
@@ -616,18 +1305,39 @@ impl PartyApp {
                         ui.separator();
 
                         ui.horizontal(|ui| {
+                            let session_running =
+                                self.session.as_ref().is_some_and(|s| !s.is_finished());
+                            let scan_errors = match cur_game!(self) {
+                                HandlerRef(h) => h.scan_errors.clone(),
+                                _ => Vec::new(),
+                            };
+                            let disabled_reason = match session_running {
+                                true => "A session is already running".to_string(),
+                                false => scan_errors.join("\n"),
+                            };
                             if ui
-                                .add_sized([150.0, 40.0], egui::Button::new("Play"))
+                                .add_enabled(
+                                    !session_running && scan_errors.is_empty(),
+                                    egui::Button::new("Play").min_size(egui::vec2(150.0, 40.0)),
+                                )
+                                .on_disabled_hover_text(disabled_reason)
                                 .clicked()
                             {
                                 self.players.clear();
+                                self.input_notice = None;
                                 self.profiles = scan_profiles(true);
+                                self.audio_sinks = scan_audio_sinks();
+                                // Reopening the keyboards and mice drops keys pressed before now
+                                self.kbm = scan_evdev_kbm();
                                 self.cur_page = MenuPage::Players;
                             }
                             if let HandlerRef(h) = cur_game!(self) {
                                 ui.add(egui::Separator::default().vertical());
                                 if h.win {
-                                    ui.label(" Proton");
+                                    match Runner::from_handler(h) {
+                                        Ok(Runner::Umu) | Err(_) => ui.label(" Proton"),
+                                        Ok(runner) => ui.label(format!(" {}", runner.label())),
+                                    };
                                 } else {
                                     ui.label("🐧 Native");
                                 }
@@ -639,6 +1349,9 @@ impl PartyApp {
                         });
 
                         if let HandlerRef(h) = cur_game!(self) {
+                            for error in &h.scan_errors {
+                                ui.label(RichText::new(error).color(Color32::LIGHT_RED));
+                            }
                             egui::ScrollArea::horizontal()
                                 .max_width(f32::INFINITY)
                                 .show(ui, |ui| {
@@ -660,39 +1373,313 @@ impl PartyApp {
                                     });
                                 });
                         }
+
+                        if let Executable { path, .. } = cur_game!(self)
+                            && path.extension().unwrap_or_default() == "exe"
+                        {
+                            ui.add_space(8.0);
+                            self.display_exec_runner(ui);
+                        }
+
+                        ui.add_space(8.0);
+                        egui::CollapsingHeader::new("Wrappers")
+                            .id_salt("game_wrappers")
+                            .show(ui, |ui| {
+                                if let HandlerRef(h) = cur_game!(self)
+                                    && !h.wrappers.is_empty()
+                                {
+                                    ui.label(format!("From handler: {}", h.wrappers.join(", ")));
+                                }
+                                let uid = cur_game!(self).uid();
+                                let wrappers = self.options.game_wrappers.entry(uid.clone()).or_default();
+                                wrapper_list_edit(ui, "wrappers_game", wrappers);
+                                if wrappers.is_empty() {
+                                    self.options.game_wrappers.remove(&uid);
+                                }
+                            });
+
+                        ui.add_space(8.0);
+                        let presets_header = egui::CollapsingHeader::new("Steam shortcuts")
+                            .id_salt("game_presets")
+                            .show(ui, |ui| {
+                                self.display_presets(ui);
+                            });
+                        if presets_header.header_response.hovered() {
+                            self.infotext = "Save players for this game as a preset and add it to Steam as a non-Steam game. Launching it from Steam, e.g. in Gaming Mode, starts the session right away without going through PartyDeck's menus. Steam needs to be restarted to show new shortcuts.".to_string();
+                        }
+
+                        ui.add_space(8.0);
+                        egui::CollapsingHeader::new("Last session logs")
+                            .id_salt("last_session_logs")
+                            .show(ui, |ui| {
+                                self.display_log_viewer(ui);
+                            });
                     });
             });
     }
 
-    fn display_page_players(&mut self, ui: &mut Ui) {
-        egui::Frame::new()
-            .inner_margin(egui::Margin {
-                left: 20,
-                right: 20,
-                top: 20,
-                bottom: 0,
-            })
-            .show(ui, |ui| {
-                egui::ScrollArea::vertical()
-                    .max_height(ui.available_height())
-                    .show(ui, |ui| {
-                        ui.heading("Players");
-                        ui.separator();
-
-                        ui.horizontal(|ui| {
-                            ui.add(
-                                egui::Image::new(egui::include_image!("../../res/BTN_SOUTH.png"))
-                                    .max_height(12.0),
-                            );
-                            ui.label("Add");
-                            ui.add(
-                                egui::Image::new(egui::include_image!("../../res/BTN_EAST.png"))
-                                    .max_height(12.0),
-                            );
-                            ui.label("Remove");
-                        });
+    fn display_presets(&mut self, ui: &mut Ui) {
+        let game = game_target(cur_game!(self));
+        let mut delete = None;
+        for (i, preset) in self.options.presets.iter().enumerate() {
+            if preset.game != game {
+                continue;
+            }
+            ui.horizontal(|ui| {
+                ui.label(&preset.name);
+                ui.label(
+                    RichText::new(format!(
+                        "{} players: {}",
+                        preset.profiles.len(),
+                        preset.profiles.join(", ")
+                    ))
+                    .weak(),
+                );
+                if ui.button("Add to Steam").clicked() {
+                    add_preset_shortcut(cur_game!(self), preset);
+                }
+                if ui.button("Delete").clicked() {
+                    delete = Some(i);
+                }
+            });
+        }
+        if let Some(i) = delete {
+            let preset = self.options.presets.remove(i);
+            if let Err(err) = remove_steam_shortcut(&preset.name) {
+                msg("Error", &format!("Couldn't remove Steam shortcut: {err}"));
+            }
+            let _ = save_cfg(&self.options);
+        }
 
-                        let mut i = 0;
+        ui.separator();
+        if self.profiles.is_empty() {
+            self.profiles = scan_profiles(true);
+        }
+        let draft = &mut self.preset_draft;
+        if draft.profiles.is_empty() {
+            draft.profiles = vec!["guest".to_string(); 2];
+            draft.vertical_two_player = self.options.vertical_two_player;
+        }
+        ui.horizontal(|ui| {
+            ui.label("Name");
+            ui.add(
+                egui::TextEdit::singleline(&mut draft.name).hint_text(format!(
+                    "{} ({} players)",
+                    cur_game!(self).name(),
+                    draft.profiles.len()
+                )),
+            );
+        });
+        let mut remove = None;
+        for (i, profile) in draft.profiles.iter_mut().enumerate() {
+            ui.horizontal(|ui| {
+                ui.label(format!("Player {}", i + 1));
+                egui::ComboBox::from_id_salt(format!("preset_profile_{i}"))
+                    .selected_text(profile.as_str())
+                    .show_ui(ui, |ui| {
+                        // The first scanned profile is the guest entry
+                        for name in self.profiles.iter().skip(1) {
+                            ui.selectable_value(profile, name.clone(), name);
+                        }
+                        ui.selectable_value(profile, "guest".to_string(), "guest");
+                    });
+                if ui.add_enabled(i > 0, egui::Button::new("Remove")).clicked() {
+                    remove = Some(i);
+                }
+            });
+        }
+        if let Some(i) = remove {
+            draft.profiles.remove(i);
+        }
+        ui.horizontal(|ui| {
+            if ui
+                .add_enabled(draft.profiles.len() < 4, egui::Button::new("Add player"))
+                .clicked()
+            {
+                draft.profiles.push("guest".to_string());
+            }
+            if draft.profiles.len() == 2 {
+                ui.checkbox(&mut draft.vertical_two_player, "Vertical split");
+            }
+        });
+        if ui.button("Save and add to Steam").clicked() {
+            let mut preset = draft.clone();
+            preset.game = game;
+            if preset.name.trim().is_empty() {
+                preset.name = format!(
+                    "{} ({} players)",
+                    cur_game!(self).name(),
+                    preset.profiles.len()
+                );
+            }
+            add_preset_shortcut(cur_game!(self), &preset);
+            self.options.presets.retain(|p| p.name != preset.name);
+            self.options.presets.push(preset);
+            self.preset_draft = SessionPreset::default();
+            let _ = save_cfg(&self.options);
+        }
+    }
+
+    fn display_exec_runner(&mut self, ui: &mut Ui) {
+        let uid = cur_game!(self).uid();
+        let mut runner = self
+            .options
+            .exec_runners
+            .get(&uid)
+            .cloned()
+            .unwrap_or_default();
+        ui.horizontal(|ui| {
+            ui.label("Runner");
+            egui::ComboBox::from_id_salt("exec_runner")
+                .selected_text(runner.label())
+                .show_ui(ui, |ui| {
+                    for option in [
+                        Runner::Umu,
+                        Runner::Wine {
+                            binary: "wine".to_string(),
+                        },
+                        Runner::Custom {
+                            template: String::new(),
+                        },
+                    ] {
+                        let selected =
+                            std::mem::discriminant(&runner) == std::mem::discriminant(&option);
+                        if ui.selectable_label(selected, option.label()).clicked() && !selected {
+                            runner = option;
+                        }
+                    }
+                });
+            match &mut runner {
+                Runner::Umu => {}
+                Runner::Wine { binary } => {
+                    ui.add(egui::TextEdit::singleline(binary).hint_text("wine"));
+                    if ui.button("Browse").clicked()
+                        && let Some(file) = FileDialog::new().set_title("Select Wine binary").pick_file()
+                    {
+                        *binary = file.to_string_lossy().to_string();
+                    }
+                }
+                Runner::Custom { template } => {
+                    ui.add(
+                        egui::TextEdit::singleline(template)
+                            .hint_text("my-wrapper --fast $EXE $ARGS")
+                            .desired_width(300.0),
+                    )
+                    .on_hover_text("$EXE is replaced by the executable and $ARGS by its arguments. Either is added to the end if left out. WINEPREFIX is set to the instance's prefix.");
+                }
+            }
+        });
+        if runner == Runner::Umu {
+            self.options.exec_runners.remove(&uid);
+        } else {
+            self.options.exec_runners.insert(uid, runner);
+        }
+    }
+
+    fn display_log_viewer(&mut self, ui: &mut Ui) {
+        let uid = cur_game!(self).uid();
+        let mut reload = false;
+        if self.log_viewer.uid != uid {
+            self.log_viewer = LogViewer {
+                files: last_session_dir(&uid)
+                    .map(|dir| session_log_files(&dir))
+                    .unwrap_or_default(),
+                uid,
+                ..Default::default()
+            };
+            reload = true;
+        }
+        if self.log_viewer.files.is_empty() {
+            ui.label("No logs yet. Logs are written the next time this game is played.");
+            return;
+        }
+
+        ui.horizontal(|ui| {
+            let files = &self.log_viewer.files;
+            let combo = egui::ComboBox::from_id_salt("log_file").show_index(
+                ui,
+                &mut self.log_viewer.selected,
+                files.len(),
+                |i| {
+                    files[i]
+                        .file_name()
+                        .map(|n| n.to_string_lossy().to_string())
+                        .unwrap_or_default()
+                },
+            );
+            if combo.changed() || ui.button("Refresh").clicked() {
+                reload = true;
+            }
+            if ui.button("Open Folder").clicked() {
+                if let Some(dir) = self.log_viewer.files[0].parent() {
+                    open_path(dir);
+                }
+            }
+        });
+
+        if reload {
+            let path = &self.log_viewer.files[self.log_viewer.selected];
+            self.log_viewer.text = match read_log_tail(path, 256 * 1024) {
+                Ok(text) => text,
+                Err(err) => format!("Couldn't read {}: {err}", path.display()),
+            };
+        }
+
+        egui::ScrollArea::vertical()
+            .id_salt("log_text")
+            .max_height(300.0)
+            .stick_to_bottom(true)
+            .show(ui, |ui| {
+                ui.add(
+                    egui::Label::new(RichText::new(&self.log_viewer.text).monospace())
+                        .wrap_mode(egui::TextWrapMode::Extend),
+                );
+            });
+    }
+
+    fn display_page_players(&mut self, ui: &mut Ui) {
+        egui::Frame::new()
+            .inner_margin(egui::Margin {
+                left: 20,
+                right: 20,
+                top: 20,
+                bottom: 0,
+            })
+            .show(ui, |ui| {
+                egui::ScrollArea::vertical()
+                    .max_height(ui.available_height())
+                    .show(ui, |ui| {
+                        ui.heading("Players");
+                        ui.separator();
+
+                        ui.horizontal(|ui| {
+                            ui.add(
+                                egui::Image::new(egui::include_image!("../../res/BTN_SOUTH.png"))
+                                    .max_height(12.0),
+                            );
+                            ui.label("Add");
+                            ui.add(
+                                egui::Image::new(egui::include_image!("../../res/BTN_EAST.png"))
+                                    .max_height(12.0),
+                            );
+                            ui.label("Remove");
+                            ui.add(egui::Separator::default().vertical());
+                            ui.label("⌨ Press a key to join, then click with your mouse. Esc to leave");
+                        });
+                        ui.label("Joy-Cons: hold L on one and R on the other to join as a pair, or SL and SR to play one sideways. On a left Joy-Con, Minus starts and Capture leaves.");
+
+                        if let Some(notice) = &self.input_notice {
+                            let mut dismiss = false;
+                            ui.horizontal(|ui| {
+                                ui.label(RichText::new(notice).color(Color32::from_rgb(230, 180, 60)));
+                                dismiss = ui.button("OK").clicked();
+                            });
+                            if dismiss {
+                                self.input_notice = None;
+                            }
+                        }
+
+                        let mut i = 0;
                         for player in &mut self.players {
                             ui.horizontal(|ui| {
                                 ui.label("👤");
@@ -706,43 +1693,306 @@ impl PartyApp {
                                 } else {
                                     ui.label(format!("Player {}", i + 1));
                                 }
-                                let pad = &self.pads[player.pad_index];
-                                let img = match pad.pad_type() {
-                                    PadType::Xbox => egui::include_image!("../../res/xbox.svg"),
-                                    PadType::PlayStation => {
-                                        egui::include_image!("../../res/playstation.svg")
-                                    }
-                                    PadType::Nintendo => {
-                                        egui::include_image!("../../res/nintendo.svg")
+                                match player.input {
+                                    PlayerInput::Pad(_) | PlayerInput::JoyConPair { .. } => {
+                                        for index in player.pad_indices() {
+                                            let pad = &self.pads[index];
+                                            let img = match pad.pad_type() {
+                                                PadType::Xbox => egui::include_image!("../../res/xbox.svg"),
+                                                PadType::PlayStation => {
+                                                    egui::include_image!("../../res/playstation.svg")
+                                                }
+                                                PadType::Nintendo => {
+                                                    egui::include_image!("../../res/nintendo.svg")
+                                                }
+                                                PadType::Unknown => {
+                                                    egui::include_image!("../../res/gamepad.svg")
+                                                }
+                                            };
+                                            ui.add(egui::Image::new(img).max_height(20.0));
+                                            if let Some(binding) = self.options.pad_bindings.get(&pad.identity())
+                                                && !binding.nickname.is_empty()
+                                            {
+                                                ui.label(&binding.nickname);
+                                            }
+                                            if let Some(id) = pad.event_id() {
+                                                ui.label(format!("({})", id));
+                                            }
+                                            if let Some(bat) = pad.battery_percent() {
+                                                ui.add(
+                                                    egui::Image::new(egui::include_image!(
+                                                        "../../res/battery.svg"
+                                                    ))
+                                                    .max_height(12.0),
+                                                );
+                                                ui.label(format!("{}%", bat));
+                                            }
+                                            if pad.joycon_side().is_some() {
+                                                ui.label(pad.fancyname());
+                                            }
+                                        }
+                                        if let PlayerInput::Pad(index) = player.input
+                                            && self.pads[index].joycon_side().is_some()
+                                        {
+                                            ui.label(RichText::new("Sideways").weak());
+                                        }
                                     }
-                                    PadType::Unknown => {
-                                        egui::include_image!("../../res/gamepad.svg")
+                                    PlayerInput::KeyboardMouse { keyboard, mouse } => {
+                                        ui.label("⌨");
+                                        ui.label(self.kbm[keyboard].name());
+                                        match mouse {
+                                            Some(mouse) => {
+                                                ui.label("🖱");
+                                                ui.label(self.kbm[mouse].name());
+                                            }
+                                            None => {
+                                                ui.label(RichText::new("Click with a mouse to pair it").weak());
+                                            }
+                                        }
                                     }
+                                }
+                                ui.label("🔊");
+                                let audio_text = match &player.audio {
+                                    AudioOutput::Default => "Default output".to_string(),
+                                    AudioOutput::Muted => "Muted".to_string(),
+                                    AudioOutput::Sink(name) => self
+                                        .audio_sinks
+                                        .iter()
+                                        .find(|sink| &sink.name == name)
+                                        .map(|sink| sink.description.clone())
+                                        .unwrap_or(name.clone()),
                                 };
-                                ui.add(egui::Image::new(img).max_height(20.0));
-                                if let Some(id) = pad.event_id() {
-                                    ui.label(format!("({})", id));
+                                egui::ComboBox::from_id_salt(format!("audio{i}"))
+                                    .selected_text(audio_text)
+                                    .show_ui(ui, |ui| {
+                                        ui.selectable_value(
+                                            &mut player.audio,
+                                            AudioOutput::Default,
+                                            "Default output",
+                                        );
+                                        for sink in &self.audio_sinks {
+                                            ui.selectable_value(
+                                                &mut player.audio,
+                                                AudioOutput::Sink(sink.name.clone()),
+                                                &sink.description,
+                                            );
+                                        }
+                                        ui.selectable_value(
+                                            &mut player.audio,
+                                            AudioOutput::Muted,
+                                            "Muted",
+                                        );
+                                    });
+                            });
+                            i += 1;
+                        }
+                        if self.players.len() > 1
+                            && ui.button("Only player 1 on speakers").clicked()
+                        {
+                            for (i, player) in self.players.iter_mut().enumerate() {
+                                player.audio = match i {
+                                    0 => AudioOutput::Default,
+                                    _ => AudioOutput::Muted,
+                                };
+                            }
+                        }
+                        if self.players.len() > 0 {
+                            ui.separator();
+                            ui.horizontal(|ui| {
+                                if ui.button("Start").clicked() {
+                                    self.start_game();
+                                }
+                                let dry_run = ui.button("Dry Run");
+                                if dry_run.clicked() {
+                                    if let Err(err) = self.export_plan() {
+                                        println!("{}", err);
+                                        msg("Dry Run Error", &format!("{err}"));
+                                    }
                                 }
-                                if let Some(bat) = pad.battery_percent() {
-                                    ui.add(
-                                        egui::Image::new(egui::include_image!(
-                                            "../../res/battery.svg"
+                            });
+                        }
+                    });
+            });
+    }
+
+    fn display_page_session(&mut self, ui: &mut Ui) {
+        let Some(session) = &self.session else {
+            self.cur_page = MenuPage::Games;
+            return;
+        };
+        let status = session.status();
+        let mut close = false;
+        egui::Frame::new()
+            .inner_margin(egui::Margin {
+                left: 20,
+                right: 20,
+                top: 20,
+                bottom: 0,
+            })
+            .show(ui, |ui| {
+                egui::ScrollArea::vertical()
+                    .max_height(ui.available_height())
+                    .show(ui, |ui| {
+                        ui.heading(format!("Session - {}", status.game));
+                        ui.separator();
+
+                        for warning in &status.warnings {
+                            ui.label(RichText::new(warning).color(Color32::from_rgb(230, 180, 60)));
+                        }
+                        if let Some(step) = &status.setup_step {
+                            ui.horizontal(|ui| {
+                                ui.spinner();
+                                ui.label(format!("Preparing the session: {step}"));
+                            });
+                        }
+                        if !status.ending && !status.finished {
+                            for instance in &status.instances {
+                                if instance.pad_connected == Some(false) && instance.state == InstanceState::Running {
+                                    ui.label(
+                                        RichText::new(format!(
+                                            "Player {}'s controller is disconnected. Reconnect it to keep playing.",
+                                            instance.player + 1
                                         ))
-                                        .max_height(12.0),
+                                        .color(Color32::from_rgb(230, 180, 60)),
                                     );
-                                    ui.label(format!("{}%", bat));
+                                }
+                            }
+                            for instance in &status.instances {
+                                let name = instance.profile.trim_start_matches('.');
+                                if instance.dismissed {
+                                    continue;
+                                }
+                                let notice = match (&instance.state, instance.exit_code) {
+                                    (InstanceState::Crashed, Some(code)) => format!(
+                                        "Player {}'s instance ({name}) crashed with exit code {code}",
+                                        instance.player + 1
+                                    ),
+                                    (InstanceState::Crashed, None) => format!(
+                                        "Player {}'s instance ({name}) lost its game process",
+                                        instance.player + 1
+                                    ),
+                                    (InstanceState::Exited, _) => format!(
+                                        "Player {}'s instance ({name}) has stopped",
+                                        instance.player + 1
+                                    ),
+                                    _ => continue,
+                                };
+                                ui.horizontal(|ui| {
+                                    ui.label(RichText::new(notice).color(Color32::from_rgb(230, 80, 80)));
+                                    if ui.button("Relaunch").clicked() {
+                                        session.relaunch_instance(instance.player);
+                                    }
+                                    if instance.state == InstanceState::Crashed
+                                        && ui
+                                            .button("Dismiss")
+                                            .on_hover_text("Stops keeping the session open for this instance")
+                                            .clicked()
+                                    {
+                                        session.dismiss_instance(instance.player);
+                                    }
+                                });
+                            }
+                        }
+
+                        egui::Grid::new("session_instances")
+                            .num_columns(9)
+                            .spacing([20.0, 8.0])
+                            .striped(true)
+                            .show(ui, |ui| {
+                                ui.strong("Player");
+                                ui.strong("Profile");
+                                ui.strong("State");
+                                ui.strong("Uptime");
+                                ui.strong("CPU");
+                                ui.strong("Memory");
+                                ui.strong("Exit code");
+                                ui.strong("Restarts");
+                                ui.label("");
+                                ui.end_row();
+
+                                for instance in &status.instances {
+                                    ui.label(format!("{}", instance.player + 1));
+                                    ui.label(instance.profile.trim_start_matches('.'));
+                                    let state = match instance.state {
+                                        InstanceState::Waiting => "Waiting",
+                                        InstanceState::Running => "Running",
+                                        InstanceState::Exited => "Exited",
+                                        InstanceState::Crashed => "Crashed",
+                                        InstanceState::Killed => "Killed",
+                                        InstanceState::FailedToStart => "Failed to start",
+                                    };
+                                    let state_label = ui.label(state);
+                                    if let Some(err) = &instance.error {
+                                        state_label.on_hover_text(err);
+                                    }
+                                    ui.label(format_duration(instance.uptime()));
+                                    if instance.state == InstanceState::Running {
+                                        ui.label(format!("{:.0}%", instance.cpu_percent));
+                                        ui.label(format_size(instance.rss));
+                                    } else {
+                                        ui.label("-");
+                                        ui.label("-");
+                                    }
+                                    match instance.exit_code {
+                                        Some(code) => ui.label(format!("{code}")),
+                                        None => ui.label("-"),
+                                    };
+                                    ui.label(format!("{}", instance.restarts));
+                                    let active = !status.ending && !status.finished;
+                                    if instance.state == InstanceState::Running && active {
+                                        let kill = ui.button(format!(
+                                            "Kill instance {}",
+                                            instance.player + 1
+                                        ));
+                                        if kill.clicked() {
+                                            session.kill_instance(instance.player);
+                                        }
+                                    } else if instance.can_relaunch() && active {
+                                        if ui.button("Relaunch").clicked() {
+                                            session.relaunch_instance(instance.player);
+                                        }
+                                    } else {
+                                        ui.label("");
+                                    }
+                                    ui.end_row();
+                                }
+                            });
+
+                        ui.separator();
+                        egui::CollapsingHeader::new("Audio")
+                            .id_salt("session_audio")
+                            .default_open(true)
+                            .show(ui, |ui| {
+                                display_session_audio(ui, session, &status);
+                            });
+
+                        ui.separator();
+                        if let Some(dir) = &status.log_dir {
+                            ui.horizontal(|ui| {
+                                ui.label(format!("Logs: {}", dir.display()));
+                                if ui.button("Open").clicked() {
+                                    open_path(dir);
                                 }
                             });
-                            i += 1;
                         }
-                        if self.players.len() > 0 {
-                            ui.separator();
-                            if ui.button("Start").clicked() {
-                                self.start_game();
+                        if status.finished {
+                            ui.label("Session ended.");
+                            if ui.button("Back to Games").clicked() {
+                                close = true;
+                            }
+                        } else if status.ending {
+                            ui.label("Ending session...");
+                        } else if ui.button("End Session").clicked() {
+                            if yesno("End Session?", "This will close every game instance. Are you sure?") {
+                                session.end();
                             }
                         }
                     });
             });
+        if close {
+            self.session = None;
+            self.cur_page = MenuPage::Games;
+        }
     }
 
     fn display_page_about(&mut self, ui: &mut Ui) {
@@ -758,43 +2008,39 @@ impl PartyApp {
     }
 
     fn handle_gamepad_gui(&mut self, raw_input: &mut egui::RawInput) {
-        let mut key: Option<egui::Key> = None;
+        let mut keys: Vec<egui::Key> = Vec::new();
+        let mut page_step = 0;
         for pad in &mut self.pads {
-            match pad.poll() {
-                Some(PadButton::ABtn) => {
-                    key = Some(Key::Enter);
-                }
-                Some(PadButton::BBtn) => {
-                    self.cur_page = MenuPage::Games;
-                }
-                Some(PadButton::XBtn) => {
-                    self.profiles = scan_profiles(false);
-                    self.cur_page = MenuPage::Profiles;
-                }
-                Some(PadButton::YBtn) => {
-                    self.cur_page = MenuPage::Settings;
-                }
-                Some(PadButton::SelectBtn) => {
-                    key = Some(Key::Tab);
-                }
-                Some(PadButton::Up) => {
-                    key = Some(Key::ArrowUp);
-                }
-                Some(PadButton::Down) => {
-                    key = Some(Key::ArrowDown);
-                }
-                Some(PadButton::Left) => {
-                    key = Some(Key::ArrowLeft);
-                }
-                Some(PadButton::Right) => {
-                    key = Some(Key::ArrowRight);
+            for button in pad.poll() {
+                match button {
+                    PadButton::ABtn => keys.push(Key::Enter),
+                    PadButton::BBtn => {
+                        self.cur_page = MenuPage::Games;
+                    }
+                    PadButton::XBtn => {
+                        self.profiles = scan_profiles(false);
+                        self.cur_page = MenuPage::Profiles;
+                    }
+                    PadButton::YBtn => {
+                        self.cur_page = MenuPage::Settings;
+                    }
+                    PadButton::SelectBtn => keys.push(Key::Tab),
+                    PadButton::Up => keys.push(Key::ArrowUp),
+                    PadButton::Down => keys.push(Key::ArrowDown),
+                    PadButton::Left => keys.push(Key::ArrowLeft),
+                    PadButton::Right => keys.push(Key::ArrowRight),
+                    PadButton::LBtn => page_step -= 1,
+                    PadButton::RBtn => page_step += 1,
+                    PadButton::StartBtn => {}
                 }
-                Some(_) => {}
-                None => {}
             }
+            self.nav_scroll -= pad.scroll() * NAV_SCROLL_SPEED;
+        }
+        if page_step != 0 {
+            self.switch_nav_page(page_step);
         }
 
-        if let Some(key) = key {
+        for key in keys {
             raw_input.events.push(egui::Event::Key {
                 key,
                 physical_key: None,
@@ -806,129 +2052,404 @@ impl PartyApp {
     }
 
     fn handle_gamepad_players(&mut self) {
+        let mut joined = Vec::new();
         for (i, pad) in self.pads.iter_mut().enumerate() {
             if is_pad_in_players(i, &self.players) {
                 continue;
             }
-            match pad.poll() {
-                Some(PadButton::ABtn) => {
-                    if self.players.len() < 4 {
-                        self.players.push(Player {
-                            pad_index: i,
-                            profname: String::new(),
-                            profselection: 0,
-                        });
+            for button in pad.poll() {
+                match button {
+                    PadButton::ABtn => {
+                        joined.push(i);
                     }
-                }
-                Some(PadButton::BBtn) => {
-                    if self.players.len() == 0 {
+                    PadButton::BBtn if self.players.is_empty() => {
                         self.cur_page = MenuPage::Games;
                     }
+                    _ => {}
                 }
-                _ => {}
             }
         }
+        for i in joined {
+            self.add_pad_player(PlayerInput::Pad(i));
+        }
+        self.handle_joycon_players();
+
+        self.handle_kbm_players();
 
         let mut i = 0;
         while i < self.players.len() {
-            match self.pads[self.players[i].pad_index].poll() {
-                Some(PadButton::BBtn) => {
-                    self.players.remove(i);
-                    continue;
-                }
-                Some(PadButton::StartBtn) => {
-                    self.start_game();
-                }
-                _ => {}
+            // Both Joy-Cons of a pair are read, so neither builds up a backlog of events
+            let buttons: Vec<PadButton> = self.players[i]
+                .pad_indices()
+                .into_iter()
+                .flat_map(|index| self.pads[index].poll())
+                .collect();
+            if buttons.iter().any(|b| matches!(b, PadButton::BBtn)) {
+                self.players.remove(i);
+                continue;
+            }
+            if buttons.iter().any(|b| matches!(b, PadButton::StartBtn)) {
+                self.start_game();
             }
             i += 1;
         }
     }
 
-    pub fn start_game(&mut self) {
-        let game = cur_game!(self).to_owned();
-        match game {
-            HandlerRef(handler) => {
-                if let Err(err) = self.start_handler_game(&handler) {
-                    println!("{}", err);
-                    msg("Launch Error", &format!("{err}"));
+    // Joy-Cons join with their shoulder buttons: L on one and R on the other pairs them, SL and
+    // SR together on one plays it sideways
+    fn handle_joycon_players(&mut self) {
+        let free = |app: &PartyApp, side: JoyConSide| -> Vec<usize> {
+            (0..app.pads.len())
+                .filter(|i| app.pads[*i].joycon_side() == Some(side))
+                .filter(|i| !is_pad_in_players(*i, &app.players))
+                .collect()
+        };
+        let left = free(self, JoyConSide::Left)
+            .into_iter()
+            .find(|i| self.pads[*i].holds_pair_button());
+        let right = free(self, JoyConSide::Right)
+            .into_iter()
+            .find(|i| self.pads[*i].holds_pair_button());
+        if let (Some(left), Some(right)) = (left, right) {
+            self.add_pad_player(PlayerInput::JoyConPair { left, right });
+        }
+
+        for side in [JoyConSide::Left, JoyConSide::Right] {
+            for i in free(self, side) {
+                if self.pads[i].holds_sideways_buttons() {
+                    self.add_pad_player(PlayerInput::Pad(i));
                 }
             }
-            Executable { path, .. } => {
-                if let Err(err) = self.start_exec_game(&path) {
-                    println!("{}", err);
-                    msg("Launch Error", &format!("{err}"));
-                }
+        }
+    }
+
+    fn handle_gamepad_remap(&mut self) {
+        let targets = self.mapping_targets();
+        for i in 0..self.pads.len() {
+            self.nav_scroll -= self.pads[i].scroll() * NAV_SCROLL_SPEED;
+            for btn in self.pads[i].poll() {
+                self.remap.pad = i;
+                let step = match btn {
+                    PadButton::BBtn => {
+                        self.cur_page = MenuPage::Settings;
+                        return;
+                    }
+                    PadButton::Up => {
+                        self.remap.row = self.remap.row.saturating_sub(1);
+                        continue;
+                    }
+                    PadButton::Down => {
+                        self.remap.row = (self.remap.row + 1).min(REMAP_ROWS - 1);
+                        continue;
+                    }
+                    PadButton::LBtn | PadButton::RBtn if !targets.is_empty() => {
+                        let selected = self.selected_mapping_target(&targets);
+                        let next = match btn {
+                            PadButton::LBtn => (selected + targets.len() - 1) % targets.len(),
+                            _ => (selected + 1) % targets.len(),
+                        };
+                        self.remap.target = Some(targets[next].0.clone());
+                        continue;
+                    }
+                    PadButton::Left => -1,
+                    PadButton::Right => 1,
+                    PadButton::ABtn => 0,
+                    _ => continue,
+                };
+                let Some((target, _)) = targets.get(self.selected_mapping_target(&targets)) else {
+                    continue;
+                };
+                let mut mapping = self.mapping_for(target);
+                adjust_mapping(&mut mapping, self.remap.row, step);
+                self.set_mapping(target, mapping);
             }
         }
-        self.cur_page = MenuPage::Games;
     }
 
-    pub fn start_handler_game(
-        &mut self,
-        handler: &Handler,
-    ) -> Result<(), Box<dyn std::error::Error>> {
-        let _ = save_cfg(&self.options);
-        log_info("Starting handler game launch");
+    // Adds a player for a pad or pair of Joy-Cons, with the profile and slot it had last time.
+    // A pair is remembered by its left Joy-Con.
+    fn add_pad_player(&mut self, input: PlayerInput) {
+        let (PlayerInput::Pad(index) | PlayerInput::JoyConPair { left: index, .. }) = input else {
+            return;
+        };
+        if self.players.len() >= 4 {
+            return;
+        }
+        let bindings = &self.options.pad_bindings;
+        let binding = bindings.get(&self.pads[index].identity());
+        let profselection = binding
+            .filter(|b| !b.profile.is_empty())
+            .and_then(|b| self.profiles.iter().position(|p| *p == b.profile))
+            .unwrap_or(0);
 
-        let mut guests = GUEST_NAMES.to_vec();
-        for player in &mut self.players {
-            if player.profselection == 0 {
-                let i = fastrand::usize(..guests.len());
-                player.profname = format!(".{}", guests[i]);
-                guests.swap_remove(i);
-            } else {
-                player.profname = self.profiles[player.profselection].to_owned();
+        // Players are kept in the order of their remembered slots; others stay where they joined
+        let slot_of = |player: &Player| {
+            let pad = &self.pads[player.pad_index()?];
+            bindings.get(&pad.identity())?.slot
+        };
+        let position = match binding.and_then(|b| b.slot) {
+            Some(slot) => self
+                .players
+                .iter()
+                .enumerate()
+                .position(|(n, p)| slot_of(p).map_or(n >= slot, |s| s > slot))
+                .unwrap_or(self.players.len()),
+            None => self.players.len(),
+        };
+        self.players.insert(
+            position,
+            Player {
+                input,
+                profname: String::new(),
+                profselection,
+                audio: AudioOutput::Default,
+            },
+        );
+    }
+
+    // Remembers the profile and slot of every pad player for the next time they join
+    fn remember_pad_bindings(&mut self) {
+        let handler_game = matches!(cur_game!(self), HandlerRef(_));
+        for (slot, player) in self.players.iter().enumerate() {
+            let Some(pad) = player.pad_index().and_then(|i| self.pads.get(i)) else {
+                continue;
+            };
+            let binding = self.options.pad_bindings.entry(pad.identity()).or_default();
+            binding.slot = Some(slot);
+            // Executables don't use profiles
+            if handler_game {
+                binding.profile = match player.profselection {
+                    0 => String::new(),
+                    n => self.profiles.get(n).cloned().unwrap_or_default(),
+                };
             }
-            create_profile(player.profname.as_str())?;
-            create_gamesave(player.profname.as_str(), handler)?;
         }
-        if handler.symlink_dir {
-            create_symlink_folder(handler)?;
+    }
+
+    // Adds and removes devices plugged in or out since the last frame
+    fn handle_hotplug(&mut self) {
+        let Some(watcher) = &self.input_watcher else {
+            return;
+        };
+        for event in watcher.poll() {
+            match event {
+                HotplugEvent::Added(path) => {
+                    let known = path.to_string_lossy();
+                    if self.pads.iter().any(|pad| pad.path() == known)
+                        || self.kbm.iter().any(|dev| dev.path() == known)
+                    {
+                        continue;
+                    }
+                    if let Some(pad) = open_gamepad(&path, self.options.disable_steam_input) {
+                        log_info(&format!(
+                            "Gamepad connected: {} ({})",
+                            pad.fancyname(),
+                            pad.path()
+                        ));
+                        self.pads.push(pad);
+                    } else if let Some(dev) = open_kbm(&path) {
+                        self.kbm.push(dev);
+                    }
+                }
+                HotplugEvent::Removed(path) => {
+                    let path = path.to_string_lossy();
+                    if let Some(index) = self.pads.iter().position(|pad| pad.path() == path) {
+                        let pad = self.pads.remove(index);
+                        log_info(&format!(
+                            "Gamepad disconnected: {} ({path})",
+                            pad.fancyname()
+                        ));
+                        if let Some(player) = remove_pad_from_players(index, &mut self.players) {
+                            self.input_notice = Some(format!(
+                                "Player {}'s controller ({}) was disconnected",
+                                player + 1,
+                                pad.fancyname()
+                            ));
+                        }
+                    } else if let Some(index) = self.kbm.iter().position(|dev| dev.path() == path) {
+                        let dev = self.kbm.remove(index);
+                        if let Some(player) = remove_kbm_from_players(index, &mut self.players) {
+                            self.input_notice = Some(format!(
+                                "Player {}'s {} ({}) was disconnected",
+                                player + 1,
+                                match dev.kind() {
+                                    KbmKind::Keyboard => "keyboard",
+                                    KbmKind::Mouse => "mouse",
+                                },
+                                dev.name()
+                            ));
+                        }
+                    }
+                }
+            }
         }
+    }
 
-        let cmd = launch_from_handler(handler, &self.pads, &self.players, &self.options)?;
-        println!("\nCOMMAND:\n{}\n", cmd);
+    // Syncs the device lists with a fresh scan. Devices that are gone are dropped the way
+    // unplugging them is, so everyone else keeps their player slot.
+    fn rescan_devices(&mut self) {
+        let pads = scan_evdev_gamepads(self.options.disable_steam_input);
+        let kbm = scan_evdev_kbm();
+        for i in (0..self.pads.len()).rev() {
+            if !pads.iter().any(|pad| pad.path() == self.pads[i].path()) {
+                self.pads.remove(i);
+                remove_pad_from_players(i, &mut self.players);
+            }
+        }
+        for i in (0..self.kbm.len()).rev() {
+            if !kbm.iter().any(|dev| dev.path() == self.kbm[i].path()) {
+                self.kbm.remove(i);
+                remove_kbm_from_players(i, &mut self.players);
+            }
+        }
+        for pad in pads {
+            if !self.pads.iter().any(|known| known.path() == pad.path()) {
+                self.pads.push(pad);
+            }
+        }
+        for dev in kbm {
+            if !self.kbm.iter().any(|known| known.path() == dev.path()) {
+                self.kbm.push(dev);
+            }
+        }
+    }
 
-        let script = if self.options.vertical_two_player {
-            PATH_RES.join("splitscreen_kwin.js")
-        } else {
-            PATH_RES.join("splitscreen_kwin_horizontal.js")
+    // A key on a free keyboard adds a player, a click on a free mouse pairs it with the
+    // keyboard player still missing one, and Escape removes that keyboard's player
+    fn handle_kbm_players(&mut self) {
+        for i in 0..self.kbm.len() {
+            for event in self.kbm[i].poll() {
+                let owner = self.players.iter().position(|p| p.has_kbm(i));
+                match (self.kbm[i].kind(), event, owner) {
+                    (KbmKind::Keyboard, KbmEvent::Key, None) if self.players.len() < 4 => {
+                        self.players.push(Player {
+                            input: PlayerInput::KeyboardMouse {
+                                keyboard: i,
+                                mouse: None,
+                            },
+                            profname: String::new(),
+                            profselection: 0,
+                            audio: AudioOutput::Default,
+                        });
+                    }
+                    (KbmKind::Keyboard, KbmEvent::Escape, Some(player)) => {
+                        self.players.remove(player);
+                    }
+                    (KbmKind::Mouse, KbmEvent::Click, None) => {
+                        let waiting = self.players.iter_mut().find(|p| {
+                            matches!(p.input, PlayerInput::KeyboardMouse { mouse: None, .. })
+                        });
+                        if let Some(Player {
+                            input: PlayerInput::KeyboardMouse { mouse, .. },
+                            ..
+                        }) = waiting
+                        {
+                            *mouse = Some(i);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    pub fn start_game(&mut self) {
+        self.remember_pad_bindings();
+        let game = cur_game!(self).to_owned();
+        let result = match game {
+            HandlerRef(handler) => self.start_handler_game(&handler),
+            Executable { path, .. } => self.start_exec_game(&path),
         };
-        kwin_dbus_start_script(script)?;
+        match result {
+            Ok(()) => self.cur_page = MenuPage::Session,
+            Err(err) => {
+                println!("{}", err);
+                msg("Launch Error", &format!("{err}"));
+                self.cur_page = MenuPage::Games;
+            }
+        }
+    }
 
-        std::process::Command::new("sh")
-            .arg("-c")
-            .arg(cmd)
-            .status()?;
+    pub fn start_handler_game(
+        &mut self,
+        handler: &Handler,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let _ = save_cfg(&self.options);
+        log_info("Starting handler game launch");
 
-        kwin_dbus_unload_script()?;
-        log_info("Handler game finished");
-        remove_guest_profiles()?;
+        resolve_profiles(&mut self.players, &self.profiles);
+        let plan =
+            launch_from_handler(handler, &self.pads, &self.kbm, &self.players, &self.options)?;
+        self.start_session(plan, Some(handler.clone()));
 
         Ok(())
     }
 
-    fn start_exec_game(&self, path: &PathBuf) -> Result<(), Box<dyn std::error::Error>> {
+    fn start_exec_game(&mut self, path: &PathBuf) -> Result<(), Box<dyn std::error::Error>> {
         let _ = save_cfg(&self.options);
         log_info("Starting executable game launch");
 
-        let cmd = launch_executable(path, &self.pads, &self.players, &self.options)?;
+        resolve_profiles(&mut self.players, &self.profiles);
+        let plan = launch_executable(path, &self.pads, &self.kbm, &self.players, &self.options)?;
+        self.start_session(plan, None);
 
-        let script = if self.options.vertical_two_player {
-            PATH_RES.join("splitscreen_kwin.js")
-        } else {
-            PATH_RES.join("splitscreen_kwin_horizontal.js")
+        Ok(())
+    }
+
+    fn start_session(&mut self, plan: LaunchPlan, handler: Option<Handler>) {
+        for warning in &plan.warnings {
+            msg("Launch warning", warning);
+        }
+        println!("\nCOMMAND:\n{}\n", plan.to_shell_script());
+        self.session = Some(Session::start(
+            plan,
+            handler,
+            self.options.session_logs_kept,
+        ));
+        // Make the game page pick up the new session's logs
+        self.log_viewer.uid.clear();
+    }
+
+    // Resolves the launch plan for the current players without starting anything, and saves it
+    // as JSON or as a shell script depending on the chosen file extension
+    fn export_plan(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        let plan = match cur_game!(self).to_owned() {
+            HandlerRef(handler) => {
+                resolve_profiles(&mut self.players, &self.profiles);
+                launch_from_handler(
+                    &handler,
+                    &self.pads,
+                    &self.kbm,
+                    &self.players,
+                    &self.options,
+                )?
+            }
+            Executable { path, .. } => {
+                resolve_profiles(&mut self.players, &self.profiles);
+                launch_executable(&path, &self.pads, &self.kbm, &self.players, &self.options)?
+            }
         };
-        kwin_dbus_start_script(script)?;
 
-        std::process::Command::new("sh")
-            .arg("-c")
-            .arg(cmd)
-            .status()?;
+        let file = match FileDialog::new()
+            .set_title("Export Launch Plan")
+            .set_directory(&*PATH_HOME)
+            .set_file_name("launch-plan.json")
+            .add_filter("JSON", &["json"])
+            .add_filter("Shell script", &["sh"])
+            .save_file()
+        {
+            Some(file) => file,
+            None => return Ok(()),
+        };
 
-        kwin_dbus_unload_script()?;
-        log_info("Executable game finished");
+        if file.extension().unwrap_or_default() == "sh" {
+            std::fs::write(&file, plan.to_shell_script())?;
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&file, std::fs::Permissions::from_mode(0o755))?;
+        } else {
+            std::fs::write(&file, plan.to_json()?)?;
+        }
+        log_info(&format!("Launch plan exported to {}", file.display()));
 
         Ok(())
     }
@@ -951,8 +2472,228 @@ fn clean_readme(src: &str) -> String {
         .collect()
 }
 
-static GUEST_NAMES: [&str; 21] = [
-    "Blinky", "Pinky", "Inky", "Clyde", "Beatrice", "Battler", "Ellie", "Joel", "Leon", "Ada",
-    "Madeline", "Theo", "Yokatta", "Wyrm", "Brodiee", "Supreme", "Conk", "Gort", "Lich", "Smores",
-    "Canary",
-];
+fn format_size(bytes: u64) -> String {
+    match bytes {
+        b if b >= 1 << 30 => format!("{:.1} GiB", b as f64 / (1u64 << 30) as f64),
+        b if b >= 1 << 20 => format!("{:.1} MiB", b as f64 / (1u64 << 20) as f64),
+        b => format!("{:.1} KiB", b as f64 / 1024.0),
+    }
+}
+
+fn open_path(path: &std::path::Path) {
+    if std::process::Command::new("xdg-open")
+        .arg(path)
+        .spawn()
+        .is_err()
+    {
+        msg("Error", &format!("Couldn't open {}!", path.display()));
+    }
+}
+
+// How "partydeck-rs launch" refers to a game
+fn game_target(game: &Game) -> String {
+    match game {
+        Executable { path, .. } => path.to_string_lossy().to_string(),
+        HandlerRef(h) => h.uid.clone(),
+    }
+}
+
+// Writes a Steam shortcut that launches the preset through the KWin launch script
+fn add_preset_shortcut(game: &Game, preset: &SessionPreset) {
+    let exe_dir = std::env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(|dir| dir.to_path_buf()))
+        .unwrap_or_default();
+    let mut script = exe_dir.join("PartyDeckKWinLaunch.sh");
+    if !script.exists() {
+        script = PATH_RES.join("PartyDeckKWinLaunch.sh");
+    }
+    let (icon, artwork) = match game {
+        HandlerRef(h) => (
+            Some(h.path_handler.join("icon.png")).filter(|p| p.exists()),
+            h.img_paths.first().cloned(),
+        ),
+        Executable { .. } => (None, None),
+    };
+    let shortcut = SteamShortcut {
+        name: preset.name.clone(),
+        exe: script,
+        start_dir: exe_dir,
+        launch_options: preset
+            .launch_args()
+            .iter()
+            .map(|arg| shell_quote(arg))
+            .collect::<Vec<_>>()
+            .join(" "),
+        icon,
+        artwork,
+    };
+    match add_steam_shortcut(&shortcut) {
+        Ok(_) => msg(
+            "Steam shortcut added",
+            &format!("Added \"{}\" to Steam.", preset.name),
+        ),
+        Err(err) => msg("Error", &format!("Couldn't add Steam shortcut: {err}")),
+    }
+}
+
+// Changes the remap page's row `row` with a pad: -1 and 1 for left and right, 0 for A.
+// A toggles and presses, left and right step through values.
+fn adjust_mapping(mapping: &mut PadMapping, row: usize, step: i32) {
+    if let Some(&button) = VirtualButton::ALL.get(row) {
+        let current = VirtualButton::ALL
+            .iter()
+            .position(|b| *b == mapping.button(button))
+            .unwrap_or(0);
+        let count = VirtualButton::ALL.len() as i32;
+        let step = match step {
+            0 => 1,
+            _ => step,
+        };
+        let next = (current as i32 + step + count) % count;
+        mapping.set_button(button, VirtualButton::ALL[next as usize]);
+        return;
+    }
+    if row < REMAP_SWAP_ROW {
+        let stick = (row - REMAP_STICK_ROWS) / 4;
+        let shape = match stick {
+            0 => &mut mapping.left_stick,
+            _ => &mut mapping.right_stick,
+        };
+        match (row - REMAP_STICK_ROWS) % 4 {
+            0 => shape.deadzone = (shape.deadzone + step as f32 * 0.02).clamp(0.0, 0.5),
+            1 => shape.curve = (shape.curve + step as f32 * 0.1).clamp(0.5, 3.0),
+            2 => shape.invert_x = !shape.invert_x,
+            _ => shape.invert_y = !shape.invert_y,
+        }
+        return;
+    }
+    match row {
+        REMAP_SWAP_ROW if step == 0 => mapping.swap_face_buttons(),
+        REMAP_RESET_ROW if step == 0 => *mapping = PadMapping::default(),
+        _ => {}
+    }
+}
+
+// Live view of a pad: its sticks before and after shaping, and the buttons held
+fn display_pad_visualiser(ui: &mut Ui, pad: &Gamepad, mapping: &PadMapping) {
+    ui.label(RichText::new(pad.fancyname()).strong());
+    let sticks = pad.sticks();
+    ui.horizontal(|ui| {
+        for (i, shape) in [mapping.left_stick, mapping.right_stick].iter().enumerate() {
+            let (rect, _) = ui.allocate_exact_size(egui::vec2(120.0, 120.0), egui::Sense::hover());
+            let painter = ui.painter();
+            let center = rect.center();
+            let radius = rect.width() / 2.0 - 4.0;
+            painter.circle_stroke(center, radius, egui::Stroke::new(1.0, Color32::GRAY));
+            painter.circle_filled(center, radius * shape.deadzone, Color32::from_gray(60));
+            let (x, y) = sticks[i];
+            painter.circle_filled(center + egui::vec2(x, y) * radius, 5.0, Color32::GRAY);
+            let (x, y) = shape.apply(x, y);
+            painter.circle_filled(
+                center + egui::vec2(x, y) * radius,
+                5.0,
+                Color32::from_rgb(0, 177, 227),
+            );
+        }
+    });
+    ui.label(RichText::new("Grey: stick as read    Blue: as sent to the game").small());
+    ui.add_space(8.0);
+    let held = pad.held_buttons();
+    ui.horizontal_wrapped(|ui| {
+        for button in VirtualButton::ALL {
+            let to = mapping.button(button);
+            let text = match to == button {
+                true => button.name().to_string(),
+                false => format!("{} → {}", button.name(), to.name()),
+            };
+            match held.contains(&button) {
+                true => ui.label(
+                    RichText::new(text)
+                        .strong()
+                        .color(Color32::from_rgb(0, 177, 227)),
+                ),
+                false => ui.label(RichText::new(text).color(Color32::GRAY)),
+            };
+        }
+    });
+}
+
+// Edits an ordered list of wrapper commands, outermost first
+fn wrapper_list_edit(ui: &mut Ui, id_salt: &str, wrappers: &mut Vec<String>) {
+    let mut remove = None;
+    let mut raise = None;
+    for (i, wrapper) in wrappers.iter_mut().enumerate() {
+        ui.horizontal(|ui| {
+            ui.add(egui::TextEdit::singleline(wrapper).desired_width(200.0));
+            if ui.add_enabled(i > 0, egui::Button::new("⬆")).clicked() {
+                raise = Some(i);
+            }
+            if ui.button("Remove").clicked() {
+                remove = Some(i);
+            }
+        });
+    }
+    if let Some(i) = raise {
+        wrappers.swap(i - 1, i);
+    }
+    if let Some(i) = remove {
+        wrappers.remove(i);
+    }
+    egui::ComboBox::from_id_salt(id_salt)
+        .selected_text("Add wrapper")
+        .show_ui(ui, |ui| {
+            for suggestion in WRAPPER_SUGGESTIONS {
+                if ui.selectable_label(false, suggestion).clicked() {
+                    wrappers.push(suggestion.to_string());
+                }
+            }
+            if ui.selectable_label(false, "Custom").clicked() {
+                wrappers.push(String::new());
+            }
+        });
+}
+
+// Per-instance volume, mute and audio focus for a running session
+fn display_session_audio(ui: &mut Ui, session: &Session, status: &SessionStatus) {
+    egui::Grid::new("session_audio_grid")
+        .num_columns(5)
+        .spacing([20.0, 8.0])
+        .show(ui, |ui| {
+            for (i, instance) in status.instances.iter().enumerate() {
+                ui.label(format!("Player {}", instance.player + 1));
+                let mut volume = instance.volume;
+                if ui
+                    .add(egui::Slider::new(&mut volume, 0.0..=1.5).custom_formatter(|v, _| {
+                        format!("{:.0}%", v * 100.0)
+                    }))
+                    .changed()
+                {
+                    session.set_volume(i, volume);
+                }
+                let mut muted = instance.muted;
+                if ui.checkbox(&mut muted, "Mute").changed() {
+                    session.set_muted(i, muted);
+                }
+                let focused = status.audio_focus == Some(i);
+                if ui
+                    .selectable_label(focused, "Focus")
+                    .on_hover_text("Lowers the volume of every other player, e.g. while this player watches a cutscene")
+                    .clicked()
+                {
+                    session.focus_audio(if focused { None } else { Some(i) });
+                }
+                let streams = ui.label(match instance.streams.len() {
+                    0 => "No audio".to_string(),
+                    1 => "1 stream".to_string(),
+                    n => format!("{n} streams"),
+                });
+                if !instance.streams.is_empty() {
+                    let names: Vec<&str> =
+                        instance.streams.iter().map(|s| s.name.as_str()).collect();
+                    streams.on_hover_text(names.join("\n"));
+                }
+                ui.end_row();
+            }
+        });
+}
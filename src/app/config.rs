@@ -1,5 +1,9 @@
+use crate::launch::{GamescopeOptions, ResourceOptions, Runner};
 use crate::paths::*;
+use crate::remap::PadMapping;
+use crate::util::PrefixStrategy;
 
+use std::collections::BTreeMap;
 use std::error::Error;
 use std::fs::File;
 use std::io::BufReader;
@@ -12,9 +16,83 @@ pub struct PartyConfig {
     pub force_sdl: bool,
     pub render_scale: i32,
     pub gamescope_sdl_backend: bool,
+    pub gamescope: GamescopeOptions,
     pub proton_version: String,
+    pub proton_extra_dirs: Vec<String>,
     pub vertical_two_player: bool,
     pub disable_steam_input: bool,
+    pub session_logs_kept: usize,
+    pub prefix_strategy: PrefixStrategy,
+    pub resources: ResourceOptions,
+    pub network_isolation: bool,
+    pub virtual_pads: bool,
+    // Wrapper commands for every game, each game by uid, and each player slot
+    pub wrappers: Vec<String>,
+    pub game_wrappers: BTreeMap<String, Vec<String>>,
+    pub slot_wrappers: Vec<Vec<String>>,
+    // Runner for each executable game, keyed by its uid
+    pub exec_runners: BTreeMap<String, Runner>,
+    pub presets: Vec<SessionPreset>,
+    // Remembered controllers, keyed by Gamepad::identity
+    pub pad_bindings: BTreeMap<String, PadBinding>,
+    // Button remaps and stick shaping by profile, and by controller model (Gamepad::model).
+    // A profile's mapping takes precedence over its controller's.
+    pub profile_mappings: BTreeMap<String, PadMapping>,
+    pub model_mappings: BTreeMap<String, PadMapping>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct PadBinding {
+    // Shown instead of the model, e.g. "Alice's blue DualSense"
+    pub nickname: String,
+    // Profile preselected when the controller joins, empty for a guest
+    pub profile: String,
+    // Player slot it last played in
+    pub slot: Option<usize>,
+}
+
+// A game and its players, saved so it can be launched directly, e.g. from a Steam shortcut
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct SessionPreset {
+    pub name: String,
+    // Handler uid, or path of an executable
+    pub game: String,
+    // Profile of each player, "guest" for a guest
+    pub profiles: Vec<String>,
+    pub vertical_two_player: bool,
+}
+
+impl SessionPreset {
+    // Arguments for "partydeck-rs launch" that start this preset
+    pub fn launch_args(&self) -> Vec<String> {
+        let mut args = vec![
+            "launch".to_string(),
+            self.game.clone(),
+            "--players".to_string(),
+            self.profiles.len().to_string(),
+            "--profiles".to_string(),
+            self.profiles.join(","),
+        ];
+        if self.profiles.len() == 2 {
+            args.push("--layout".to_string());
+            args.push(match self.vertical_two_player {
+                true => "vertical".to_string(),
+                false => "horizontal".to_string(),
+            });
+        }
+        args
+    }
+}
+
+impl PartyConfig {
+    pub fn pad_mapping(&self, profile: &str, model: &str) -> Option<&PadMapping> {
+        self.profile_mappings
+            .get(profile)
+            .or_else(|| self.model_mappings.get(model))
+            .filter(|mapping| !mapping.is_default())
+    }
 }
 
 pub fn load_cfg() -> PartyConfig {
@@ -43,9 +121,24 @@ impl Default for PartyConfig {
             force_sdl: false,
             render_scale: 100,
             gamescope_sdl_backend: true,
+            gamescope: GamescopeOptions::default(),
             proton_version: String::new(),
+            proton_extra_dirs: Vec::new(),
             vertical_two_player: true,
             disable_steam_input: true,
+            session_logs_kept: 10,
+            prefix_strategy: PrefixStrategy::Shared,
+            resources: ResourceOptions::default(),
+            network_isolation: false,
+            virtual_pads: false,
+            wrappers: Vec::new(),
+            game_wrappers: BTreeMap::new(),
+            slot_wrappers: vec![Vec::new(); 4],
+            exec_runners: BTreeMap::new(),
+            presets: Vec::new(),
+            pad_bindings: BTreeMap::new(),
+            profile_mappings: BTreeMap::new(),
+            model_mappings: BTreeMap::new(),
         }
     }
 }
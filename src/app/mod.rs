@@ -2,4 +2,4 @@ mod app;
 mod config;
 
 pub use app::PartyApp;
-pub use config::PartyConfig;
+pub use config::{PartyConfig, load_cfg};
@@ -0,0 +1,311 @@
+use crate::app::{PartyConfig, load_cfg};
+use crate::game::{Game, find_game};
+use crate::input::*;
+use crate::launch::*;
+use crate::session::{InstanceState, Session};
+use crate::util::{AudioOutput, create_profile, remove_guest_profile, scan_profiles};
+
+use std::error::Error;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+const USAGE: &str = "Usage:
+  partydeck-rs [--fullscreen]
+  partydeck-rs launch <uid|exe> [--players N] [--profiles a,b,...] [--pads auto|ID,...] [--layout vertical|horizontal]
+  partydeck-rs dry-run <uid|exe> [--players N] [--profiles a,b,...] [--pads auto|ID,...] [--layout vertical|horizontal] [--format json|sh] [--output FILE]
+
+Profiles that don't exist yet are created, \"guest\" picks a guest profile.
+Pads are event device numbers, e.g. 5 for /dev/input/event5; auto uses the first connected pads.
+
+Exit codes for launch:
+  0  every instance exited cleanly, or the session was ended
+  1  invalid arguments, or the session couldn't be started
+  2  an instance crashed or failed to start";
+
+const EXIT_OK: i32 = 0;
+const EXIT_ERROR: i32 = 1;
+const EXIT_CRASHED: i32 = 2;
+
+// Set by SIGINT/SIGTERM to end a headless session
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+// Handles command-line subcommands. Returns an exit code if one was run, None to start the GUI.
+pub fn run(args: &[String]) -> Option<i32> {
+    let subcommand = args.get(1)?;
+    let result = match subcommand.as_str() {
+        "launch" => launch(&args[2..]),
+        "dry-run" => dry_run(&args[2..]).map(|_| EXIT_OK),
+        "help" | "--help" | "-h" => {
+            println!("{USAGE}");
+            Ok(EXIT_OK)
+        }
+        _ => return None,
+    };
+    match result {
+        Ok(code) => Some(code),
+        Err(err) => {
+            eprintln!("{err}");
+            Some(EXIT_ERROR)
+        }
+    }
+}
+
+// Parsed "--name value" options shared by the subcommands
+struct Opts {
+    target: String,
+    players: Option<usize>,
+    profiles: Vec<String>,
+    pads: Option<String>,
+    layout: Option<String>,
+    format: String,
+    output: Option<String>,
+}
+
+fn parse_opts(args: &[String]) -> Result<Opts, Box<dyn Error>> {
+    let mut opts = Opts {
+        target: String::new(),
+        players: None,
+        profiles: Vec::new(),
+        pads: None,
+        layout: None,
+        format: "json".to_string(),
+        output: None,
+    };
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        if !arg.starts_with("--") {
+            if !opts.target.is_empty() {
+                return Err(format!("Unexpected argument: {arg}\n{USAGE}").into());
+            }
+            opts.target = arg.clone();
+            continue;
+        }
+        let value = iter
+            .next()
+            .ok_or_else(|| format!("Missing value for {arg}"))?
+            .clone();
+        match arg.as_str() {
+            "--players" => {
+                let n = value
+                    .parse::<usize>()
+                    .map_err(|_| format!("Invalid player count: {value}"))?;
+                if !(1..=4).contains(&n) {
+                    return Err("Player count must be between 1 and 4".into());
+                }
+                opts.players = Some(n);
+            }
+            "--profiles" => {
+                opts.profiles = value.split(',').map(|s| s.trim().to_string()).collect();
+            }
+            "--pads" => opts.pads = Some(value),
+            "--layout" => opts.layout = Some(value),
+            "--format" => opts.format = value,
+            "--output" => opts.output = Some(value),
+            _ => return Err(format!("Unknown option: {arg}\n{USAGE}").into()),
+        }
+    }
+    if opts.target.is_empty() {
+        return Err(format!("No game given\n{USAGE}").into());
+    }
+    Ok(opts)
+}
+
+// Builds the player list from the requested profile names and pads.
+// With `create_profiles`, named profiles that don't exist yet are created.
+fn build_players(
+    opts: &Opts,
+    profiles: &mut Vec<String>,
+    pads: &[Gamepad],
+    create_profiles: bool,
+) -> Result<Vec<Player>, Box<dyn Error>> {
+    let count = opts.players.unwrap_or(opts.profiles.len().max(1));
+    let pad_indices = select_pads(opts, pads, count)?;
+    let mut players = Vec::new();
+    for (i, pad_index) in pad_indices.into_iter().enumerate() {
+        let profselection = match opts.profiles.get(i) {
+            None => 0,
+            Some(name) if name.eq_ignore_ascii_case("guest") => 0,
+            Some(name) => match profiles.iter().position(|p| p == name) {
+                Some(index) => index,
+                None if create_profiles => {
+                    create_profile(name)?;
+                    profiles.push(name.clone());
+                    profiles.len() - 1
+                }
+                None => return Err(format!("Profile \"{name}\" doesn't exist").into()),
+            },
+        };
+        players.push(Player {
+            input: PlayerInput::Pad(pad_index),
+            profname: String::new(),
+            profselection,
+            audio: AudioOutput::Default,
+        });
+    }
+    Ok(players)
+}
+
+// Picks the pad of each player: the first `count` pads for "auto", or the listed event ids
+fn select_pads(opts: &Opts, pads: &[Gamepad], count: usize) -> Result<Vec<usize>, Box<dyn Error>> {
+    let ids = match opts.pads.as_deref() {
+        None | Some("auto") => {
+            if pads.len() < count {
+                return Err(format!(
+                    "{count} players need {count} pads, found {}. Connect more pads or pass fewer --players.",
+                    pads.len()
+                )
+                .into());
+            }
+            return Ok((0..count).collect());
+        }
+        Some(ids) => ids.split(',').map(|id| id.trim()).collect::<Vec<_>>(),
+    };
+    if ids.len() != count {
+        return Err(format!("{count} players need {count} pads, got {}", ids.len()).into());
+    }
+    ids.iter()
+        .map(|id| {
+            pads.iter()
+                .position(|pad| pad.event_id() == Some(id))
+                .ok_or_else(|| format!("Pad {id} not found").into())
+        })
+        .collect()
+}
+
+fn apply_layout(opts: &Opts, cfg: &mut PartyConfig) -> Result<(), Box<dyn Error>> {
+    match opts.layout.as_deref() {
+        None => {}
+        Some("vertical") => cfg.vertical_two_player = true,
+        Some("horizontal") => cfg.vertical_two_player = false,
+        Some(other) => {
+            return Err(
+                format!("Unknown layout: {other} (expected vertical or horizontal)").into(),
+            );
+        }
+    }
+    Ok(())
+}
+
+// Builds the launch plan the GUI would for the same game, players and settings
+fn build_plan(opts: &Opts, create_profiles: bool) -> Result<(Game, LaunchPlan), Box<dyn Error>> {
+    let mut cfg = load_cfg();
+    apply_layout(opts, &mut cfg)?;
+    let game = find_game(&opts.target).ok_or_else(|| format!("Game not found: {}", opts.target))?;
+    let mut profiles = scan_profiles(true);
+    let pads = scan_evdev_gamepads(cfg.disable_steam_input);
+    // Keyboards and mice aren't played with here, but are still masked from every instance
+    let kbm = scan_evdev_kbm();
+    let mut players = build_players(opts, &mut profiles, &pads, create_profiles)?;
+
+    let plan = match &game {
+        Game::HandlerRef(h) => {
+            resolve_profiles(&mut players, &profiles);
+            launch_from_handler(h, &pads, &kbm, &players, &cfg)?
+        }
+        Game::Executable { path, .. } => {
+            resolve_profiles(&mut players, &profiles);
+            launch_executable(path, &pads, &kbm, &players, &cfg)?
+        }
+    };
+    for warning in &plan.warnings {
+        eprintln!("Warning: {warning}");
+    }
+    Ok((game, plan))
+}
+
+// Runs a session without the GUI and waits for it to end
+fn launch(args: &[String]) -> Result<i32, Box<dyn Error>> {
+    let opts = parse_opts(args)?;
+    let (game, plan) = build_plan(&opts, true)?;
+    let handler = match game {
+        Game::HandlerRef(h) => Some(h),
+        Game::Executable { .. } => None,
+    };
+
+    unsafe {
+        libc::signal(
+            libc::SIGINT,
+            on_signal as extern "C" fn(libc::c_int) as libc::sighandler_t,
+        );
+        libc::signal(
+            libc::SIGTERM,
+            on_signal as extern "C" fn(libc::c_int) as libc::sighandler_t,
+        );
+    }
+    let mut session = Session::start(plan, handler, load_cfg().session_logs_kept);
+    let mut end_sent = false;
+    while !session.is_finished() {
+        if INTERRUPTED.load(Ordering::Relaxed) && !end_sent {
+            eprintln!("Ending session...");
+            session.end();
+            end_sent = true;
+        }
+        // There's nothing to relaunch from here, so crashes don't hold the session open
+        for instance in session.status().instances {
+            if instance.state == InstanceState::Crashed && !instance.dismissed {
+                session.dismiss_instance(instance.player);
+            }
+        }
+        std::thread::sleep(Duration::from_millis(200));
+    }
+    session.wait();
+
+    let status = session.status();
+    // Only this session's guests, the GUI may be running a session with others
+    for instance in &status.instances {
+        if let Err(err) = remove_guest_profile(&instance.profile) {
+            eprintln!("Couldn't remove guest profile {}: {err}", instance.profile);
+        }
+    }
+    for warning in &status.warnings {
+        eprintln!("Warning: {warning}");
+    }
+    if let Some(dir) = &status.log_dir {
+        eprintln!("Logs: {}", dir.display());
+    }
+    let failed = status
+        .instances
+        .iter()
+        .filter(|i| {
+            matches!(
+                i.state,
+                InstanceState::Crashed | InstanceState::FailedToStart
+            )
+        })
+        .collect::<Vec<_>>();
+    for instance in &failed {
+        eprintln!(
+            "Player {}'s instance {}",
+            instance.player + 1,
+            match instance.state {
+                InstanceState::Crashed => "crashed",
+                _ => "failed to start",
+            }
+        );
+    }
+    Ok(if failed.is_empty() {
+        EXIT_OK
+    } else {
+        EXIT_CRASHED
+    })
+}
+
+extern "C" fn on_signal(_: libc::c_int) {
+    INTERRUPTED.store(true, Ordering::Relaxed);
+}
+
+fn dry_run(args: &[String]) -> Result<(), Box<dyn Error>> {
+    let opts = parse_opts(args)?;
+    let (_, plan) = build_plan(&opts, false)?;
+
+    let out = match opts.format.as_str() {
+        "json" => plan.to_json()?,
+        "sh" => plan.to_shell_script(),
+        other => return Err(format!("Unknown format: {other} (expected json or sh)").into()),
+    };
+    match opts.output {
+        Some(path) => std::fs::write(path, out)?,
+        None => println!("{out}"),
+    }
+    Ok(())
+}
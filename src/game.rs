@@ -34,6 +34,13 @@ impl Game {
             Game::HandlerRef(handler) => handler.display(),
         }
     }
+    // Identifies the game in session log directory names
+    pub fn uid(&self) -> String {
+        match self {
+            Game::Executable { filename, .. } => exec_uid(filename),
+            Game::HandlerRef(handler) => handler.uid.clone(),
+        }
+    }
     pub fn icon(&self) -> ImageSource {
         match self {
             Game::Executable { .. } => egui::include_image!("../res/executable_icon.png"),
@@ -82,6 +89,37 @@ pub fn scan_all_games() -> Vec<Game> {
     games
 }
 
+pub fn exec_uid(filename: &str) -> String {
+    filename
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+// Finds a game by handler uid, or by path for standalone executables
+pub fn find_game(target: &str) -> Option<Game> {
+    if let Some(h) = scan_handlers().into_iter().find(|h| h.uid == target) {
+        return Some(Game::HandlerRef(h));
+    }
+    let path = PathBuf::from(target);
+    if path.is_file() {
+        let filename = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or("")
+            .to_string();
+        let path = path.canonicalize().unwrap_or(path);
+        return Some(Game::Executable { path, filename });
+    }
+    None
+}
+
 pub fn add_game() -> Result<(), Box<dyn Error>> {
     let file = FileDialog::new()
         .set_title("Select Linux/Windows Program or PartyDeck Handler (.pdh)")
@@ -1,3 +1,4 @@
+use crate::launch::GamescopeOverrides;
 use crate::paths::*;
 use crate::util::*;
 
@@ -28,6 +29,19 @@ pub struct Handler {
     pub copy_instead_paths: Vec<String>,
     pub remove_paths: Vec<String>,
     pub dll_overrides: Vec<String>,
+    // Commands the game is run through inside bwrap, e.g. "gamemoderun"
+    pub wrappers: Vec<String>,
+    pub proton: String,
+    pub proton_min: String,
+    pub runner: String,
+    pub wine_path: String,
+    pub runner_cmd: String,
+    // How to tell an instance is ready before starting the next one, see launch::Readiness
+    pub ready: String,
+    pub ready_target: String,
+    pub ready_delay: Option<f64>,
+    pub ready_timeout: Option<f64>,
+    pub gamescope: GamescopeOverrides,
 
     pub path_goldberg: String,
     pub steam_appid: Option<String>,
@@ -38,6 +52,9 @@ pub struct Handler {
     pub linux_unique_localshare: bool,
     pub linux_unique_config: bool,
     pub game_unique_paths: Vec<String>,
+
+    // Problems found while scanning that prevent the game from launching
+    pub scan_errors: Vec<String>,
 }
 
 impl Handler {
@@ -112,6 +129,36 @@ impl Handler {
                         .collect()
                 })
                 .unwrap_or_default(),
+            wrappers: json["game.wrappers"]
+                .as_array()
+                .map(|arr| {
+                    arr.iter()
+                        .map(|v| v.as_str().unwrap_or_default().to_string())
+                        .collect()
+                })
+                .unwrap_or_default(),
+            proton: json["game.proton"].as_str().unwrap_or_default().to_string(),
+            proton_min: json["game.proton_min"]
+                .as_str()
+                .unwrap_or_default()
+                .to_string(),
+            runner: json["game.runner"].as_str().unwrap_or_default().to_string(),
+            wine_path: json["game.wine_path"]
+                .as_str()
+                .unwrap_or_default()
+                .to_string(),
+            runner_cmd: json["game.runner_cmd"]
+                .as_str()
+                .unwrap_or_default()
+                .to_string(),
+            ready: json["game.ready"].as_str().unwrap_or_default().to_string(),
+            ready_target: json["game.ready_target"]
+                .as_str()
+                .unwrap_or_default()
+                .to_string(),
+            ready_delay: json["game.ready_delay"].as_f64(),
+            ready_timeout: json["game.ready_timeout"].as_f64(),
+            gamescope: GamescopeOverrides::from_json(&json),
 
             path_goldberg: json["steam.api_path"]
                 .as_str()
@@ -141,6 +188,7 @@ impl Handler {
                         .collect()
                 })
                 .unwrap_or_default(),
+            scan_errors: Vec::new(),
         };
 
         if !handler
@@ -204,6 +252,7 @@ impl Handler {
 pub fn scan_handlers() -> Vec<Handler> {
     let mut out: Vec<Handler> = Vec::new();
     let handlers_path = PATH_PARTY.join("handlers");
+    let runtimes = scan_runtimes();
 
     let entries = match std::fs::read_dir(handlers_path) {
         Ok(entries) => entries,
@@ -226,7 +275,16 @@ pub fn scan_handlers() -> Vec<Handler> {
         if !json_path.exists() {
             continue;
         }
-        if let Ok(handler) = Handler::new(&json_path) {
+        if let Ok(mut handler) = Handler::new(&json_path) {
+            if !handler.win
+                && !handler.runtime.is_empty()
+                && find_runtime(&runtimes, &handler.runtime).is_none()
+            {
+                handler.scan_errors.push(format!(
+                    "Needs the Steam Linux Runtime ({}), which isn't installed. Install it from Steam's Tools section.",
+                    handler.runtime
+                ));
+            }
             out.push(handler);
         }
     }
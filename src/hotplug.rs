@@ -0,0 +1,74 @@
+use crossbeam_channel::{Receiver, unbounded};
+use std::error::Error;
+use std::path::PathBuf;
+
+pub enum HotplugEvent {
+    // An event node appeared, or its permissions changed so it may be openable now
+    Added(PathBuf),
+    Removed(PathBuf),
+}
+
+// Watches /dev/input with inotify so devices can be added and removed without a rescan
+pub struct InputWatcher {
+    rx: Receiver<HotplugEvent>,
+}
+
+impl InputWatcher {
+    pub fn start() -> Result<InputWatcher, Box<dyn Error>> {
+        let fd = unsafe { libc::inotify_init1(libc::IN_CLOEXEC) };
+        if fd < 0 {
+            return Err(format!("inotify: {}", std::io::Error::last_os_error()).into());
+        }
+        // udev creates nodes root-only and fixes their permissions right after, hence IN_ATTRIB
+        let mask = libc::IN_CREATE | libc::IN_DELETE | libc::IN_ATTRIB;
+        if unsafe { libc::inotify_add_watch(fd, c"/dev/input".as_ptr(), mask) } < 0 {
+            let err = std::io::Error::last_os_error();
+            unsafe { libc::close(fd) };
+            return Err(format!("Couldn't watch /dev/input: {err}").into());
+        }
+
+        let (tx, rx) = unbounded();
+        std::thread::spawn(move || {
+            let mut buf = [0u8; 4096];
+            loop {
+                let len =
+                    unsafe { libc::read(fd, buf.as_mut_ptr() as *mut libc::c_void, buf.len()) };
+                if len <= 0 {
+                    break;
+                }
+                let mut events = Vec::new();
+                let mut pos = 0;
+                while pos + size_of::<libc::inotify_event>() <= len as usize {
+                    let event = unsafe {
+                        std::ptr::read_unaligned(buf[pos..].as_ptr() as *const libc::inotify_event)
+                    };
+                    let name_start = pos + size_of::<libc::inotify_event>();
+                    let name = &buf[name_start..name_start + event.len as usize];
+                    pos = name_start + event.len as usize;
+
+                    // The name is NUL-padded; only evdev nodes are of interest
+                    let name = String::from_utf8_lossy(name)
+                        .trim_end_matches('\0')
+                        .to_string();
+                    if !name.starts_with("event") {
+                        continue;
+                    }
+                    let path = PathBuf::from("/dev/input").join(name);
+                    events.push(match event.mask & libc::IN_DELETE {
+                        0 => HotplugEvent::Added(path),
+                        _ => HotplugEvent::Removed(path),
+                    });
+                }
+                if events.into_iter().any(|event| tx.send(event).is_err()) {
+                    break;
+                }
+            }
+            unsafe { libc::close(fd) };
+        });
+        Ok(InputWatcher { rx })
+    }
+
+    pub fn poll(&self) -> Vec<HotplugEvent> {
+        self.rx.try_iter().collect()
+    }
+}
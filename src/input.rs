@@ -1,23 +1,176 @@
+use crate::util::AudioOutput;
+
 pub struct Player {
-    pub pad_index: usize,
+    pub input: PlayerInput,
     pub profname: String,
     pub profselection: usize,
+    pub audio: AudioOutput,
+}
+
+// What a player plays with, as indices into the scanned gamepads or keyboards and mice
+#[derive(Clone, Copy, PartialEq)]
+pub enum PlayerInput {
+    // A single Joy-Con is played sideways
+    Pad(usize),
+    // Two Joy-Cons played together as one pad
+    JoyConPair {
+        left: usize,
+        right: usize,
+    },
+    // The mouse is paired after the keyboard joins, by clicking with it
+    KeyboardMouse {
+        keyboard: usize,
+        mouse: Option<usize>,
+    },
+}
+
+impl Player {
+    // The player's pad, or the left Joy-Con of a pair
+    pub fn pad_index(&self) -> Option<usize> {
+        self.pad_indices().first().copied()
+    }
+
+    pub fn pad_indices(&self) -> Vec<usize> {
+        match self.input {
+            PlayerInput::Pad(index) => vec![index],
+            PlayerInput::JoyConPair { left, right } => vec![left, right],
+            PlayerInput::KeyboardMouse { .. } => Vec::new(),
+        }
+    }
+
+    // Whether the keyboard or mouse at `index` of the scanned list belongs to this player
+    pub fn has_kbm(&self, index: usize) -> bool {
+        match self.input {
+            PlayerInput::Pad(_) | PlayerInput::JoyConPair { .. } => false,
+            PlayerInput::KeyboardMouse { keyboard, mouse } => {
+                keyboard == index || mouse == Some(index)
+            }
+        }
+    }
 }
 
 pub fn is_pad_in_players(index: usize, players: &Vec<Player>) -> bool {
     for player in players {
-        if player.pad_index == index {
+        if player.pad_indices().contains(&index) {
             return true;
         }
     }
     false
 }
 
+// Keeps players pointing at the right pads after the pad at `index` was unplugged.
+// The player who was using it is removed; returns their position.
+pub fn remove_pad_from_players(index: usize, players: &mut Vec<Player>) -> Option<usize> {
+    let owner = players
+        .iter()
+        .position(|p| p.pad_indices().contains(&index));
+    if let Some(owner) = owner {
+        players.remove(owner);
+    }
+    let shift = |i: &mut usize| {
+        if *i > index {
+            *i -= 1;
+        }
+    };
+    for player in players.iter_mut() {
+        match &mut player.input {
+            PlayerInput::Pad(i) => shift(i),
+            PlayerInput::JoyConPair { left, right } => {
+                shift(left);
+                shift(right);
+            }
+            PlayerInput::KeyboardMouse { .. } => {}
+        }
+    }
+    owner
+}
+
+// Same as remove_pad_from_players for keyboards and mice. A player losing their keyboard is
+// removed, one losing their mouse keeps playing and can click with another.
+pub fn remove_kbm_from_players(index: usize, players: &mut Vec<Player>) -> Option<usize> {
+    let owner = players.iter().position(|p| p.has_kbm(index));
+    if let Some(owner) = owner
+        && let PlayerInput::KeyboardMouse { keyboard, .. } = players[owner].input
+        && keyboard == index
+    {
+        players.remove(owner);
+    }
+    let shift = |i: &mut usize| {
+        if *i > index {
+            *i -= 1;
+        }
+    };
+    for player in players.iter_mut() {
+        if let PlayerInput::KeyboardMouse { keyboard, mouse } = &mut player.input {
+            shift(keyboard);
+            if *mouse == Some(index) {
+                *mouse = None;
+            }
+            if let Some(mouse) = mouse {
+                shift(mouse);
+            }
+        }
+    }
+    owner
+}
+
+use crate::remap::{VirtualButton, normalize_axis};
 use evdev::*;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+// How far the left stick must be pushed to count as a direction, and how far back it must come
+// to let go of it
+const STICK_PRESS: f32 = 0.6;
+const STICK_RELEASE: f32 = 0.4;
+// Holding a direction repeats it after REPEAT_DELAY, then every REPEAT_INTERVAL
+const REPEAT_DELAY: Duration = Duration::from_millis(400);
+const REPEAT_INTERVAL: Duration = Duration::from_millis(110);
 
 pub struct Gamepad {
     path: String,
     dev: Device,
+    ranges: HashMap<AbsoluteAxisCode, (i32, i32)>,
+    nav: NavState,
+}
+
+// What a pad is holding for menu navigation
+#[derive(Default)]
+struct NavState {
+    hat: (i32, i32),
+    stick: (f32, f32),
+    // Left and right trigger, from 0 to 1
+    triggers: (f32, f32),
+    // The direction being held, and when it next repeats
+    held: Option<(PadButton, Instant)>,
+}
+
+impl NavState {
+    // The d-pad takes precedence over the stick
+    fn direction(&self) -> Option<PadButton> {
+        match self.hat {
+            (-1, _) => return Some(PadButton::Left),
+            (1, _) => return Some(PadButton::Right),
+            (_, -1) => return Some(PadButton::Up),
+            (_, 1) => return Some(PadButton::Down),
+            _ => {}
+        }
+        let (x, y) = self.stick;
+        let threshold = match self.held {
+            Some(_) => STICK_RELEASE,
+            None => STICK_PRESS,
+        };
+        if x.abs().max(y.abs()) < threshold {
+            return None;
+        }
+        Some(match x.abs() > y.abs() {
+            true if x < 0.0 => PadButton::Left,
+            true => PadButton::Right,
+            false if y < 0.0 => PadButton::Up,
+            false => PadButton::Down,
+        })
+    }
 }
 
 pub enum PadType {
@@ -26,6 +179,22 @@ pub enum PadType {
     Nintendo,
     Unknown,
 }
+#[derive(Clone, Copy, PartialEq)]
+pub enum JoyConSide {
+    Left,
+    Right,
+}
+
+// Each Joy-Con is its own device with hid-nintendo
+pub fn joycon_side(id: InputId) -> Option<JoyConSide> {
+    match (id.vendor(), id.product()) {
+        (0x057e, 0x2006) => Some(JoyConSide::Left),
+        (0x057e, 0x2007) => Some(JoyConSide::Right),
+        _ => None,
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
 pub enum PadButton {
     Left,
     Right,
@@ -37,12 +206,19 @@ pub enum PadButton {
     YBtn,
     StartBtn,
     SelectBtn,
+    LBtn,
+    RBtn,
 }
 impl Gamepad {
     pub fn name(&self) -> &str {
         self.dev.name().unwrap_or_else(|| "")
     }
     pub fn fancyname(&self) -> &str {
+        match self.joycon_side() {
+            Some(JoyConSide::Left) => return "Joy-Con (L)",
+            Some(JoyConSide::Right) => return "Joy-Con (R)",
+            None => {}
+        }
         match self.dev.input_id().vendor() {
             0x045e => "Xbox Controller",
             0x054c => "PS Controller",
@@ -53,6 +229,77 @@ impl Gamepad {
     pub fn path(&self) -> &str {
         &self.path
     }
+    pub fn joycon_side(&self) -> Option<JoyConSide> {
+        joycon_side(self.dev.input_id())
+    }
+
+    // Vendor and product, shared by every controller of the same model
+    pub fn model(&self) -> String {
+        let id = self.dev.input_id();
+        format!("{:04x}:{:04x}", id.vendor(), id.product())
+    }
+
+    // Identifies this controller across reconnects and reboots: its vendor and product, plus
+    // its serial or Bluetooth address if it reports one, otherwise the port it's plugged into
+    pub fn identity(&self) -> String {
+        let unique = match self.dev.unique_name() {
+            Some(uniq) if !uniq.is_empty() => uniq,
+            _ => self.dev.physical_path().unwrap_or(""),
+        };
+        format!("{}:{unique}", self.model())
+    }
+
+    // Every device node of the physical controller this pad belongs to: the pad itself, other
+    // event nodes such as motion sensors and touchpads, and its js and hidraw nodes
+    pub fn device_nodes(&self) -> Vec<String> {
+        let mut nodes = vec![self.path.clone()];
+        let Some(name) = Path::new(&self.path).file_name() else {
+            return nodes;
+        };
+        // The input device is <physical device>/input/inputN
+        let Ok(input) =
+            std::fs::canonicalize(Path::new("/sys/class/input").join(name).join("device"))
+        else {
+            return nodes;
+        };
+        let Some(root) = input
+            .parent()
+            .filter(|dir| dir.ends_with("input"))
+            .and_then(|dir| dir.parent())
+        else {
+            return nodes;
+        };
+        // Virtual devices like uinput pads share one parent, they aren't siblings
+        if root.starts_with("/sys/devices/virtual") {
+            return nodes;
+        }
+
+        for entry in walkdir::WalkDir::new(root)
+            .max_depth(3)
+            .into_iter()
+            .flatten()
+        {
+            let name = entry.file_name().to_string_lossy();
+            let (prefix, dir) = if name.starts_with("event") {
+                ("event", "/dev/input")
+            } else if name.starts_with("js") {
+                ("js", "/dev/input")
+            } else if name.starts_with("hidraw") {
+                ("hidraw", "/dev")
+            } else {
+                continue;
+            };
+            let number = &name[prefix.len()..];
+            if number.is_empty() || !number.chars().all(|c| c.is_ascii_digit()) {
+                continue;
+            }
+            let node = format!("{dir}/{name}");
+            if !nodes.contains(&node) && Path::new(&node).exists() {
+                nodes.push(node);
+            }
+        }
+        nodes
+    }
 
     pub fn pad_type(&self) -> PadType {
         match self.dev.input_id().vendor() {
@@ -121,84 +368,347 @@ impl Gamepad {
         }
         None
     }
-    pub fn poll(&mut self) -> Option<PadButton> {
-        let mut btn: Option<PadButton> = None;
-        if let Ok(events) = self.dev.fetch_events() {
-            for event in events {
-                btn = match event.destructure() {
-                    EventSummary::Key(_, KeyCode::BTN_SOUTH, 1) => Some(PadButton::ABtn),
-                    EventSummary::Key(_, KeyCode::BTN_EAST, 1) => Some(PadButton::BBtn),
-                    EventSummary::Key(_, KeyCode::BTN_NORTH, 1) => Some(PadButton::XBtn),
-                    EventSummary::Key(_, KeyCode::BTN_WEST, 1) => Some(PadButton::YBtn),
-                    EventSummary::Key(_, KeyCode::BTN_START, 1) => Some(PadButton::StartBtn),
-                    EventSummary::Key(_, KeyCode::BTN_SELECT, 1) => Some(PadButton::SelectBtn),
-                    EventSummary::AbsoluteAxis(_, AbsoluteAxisCode::ABS_HAT0X, -1) => {
-                        Some(PadButton::Left)
-                    }
-                    EventSummary::AbsoluteAxis(_, AbsoluteAxisCode::ABS_HAT0X, 1) => {
-                        Some(PadButton::Right)
-                    }
-                    EventSummary::AbsoluteAxis(_, AbsoluteAxisCode::ABS_HAT0Y, -1) => {
-                        Some(PadButton::Up)
-                    }
-                    EventSummary::AbsoluteAxis(_, AbsoluteAxisCode::ABS_HAT0Y, 1) => {
-                        Some(PadButton::Down)
+    // Current position of both sticks from -1 to 1, read without taking the pad's events
+    pub fn sticks(&self) -> [(f32, f32); 2] {
+        let Ok(state) = self.dev.get_abs_state() else {
+            return [(0.0, 0.0); 2];
+        };
+        let axis = |code: AbsoluteAxisCode| {
+            let info = state[code.0 as usize];
+            normalize_axis(info.value, info.minimum, info.maximum)
+        };
+        [
+            (axis(AbsoluteAxisCode::ABS_X), axis(AbsoluteAxisCode::ABS_Y)),
+            (
+                axis(AbsoluteAxisCode::ABS_RX),
+                axis(AbsoluteAxisCode::ABS_RY),
+            ),
+        ]
+    }
+    fn holds(&self, keys: &[KeyCode]) -> bool {
+        self.dev
+            .get_key_state()
+            .is_ok_and(|held| keys.iter().all(|key| held.contains(*key)))
+    }
+    // A Joy-Con's L or R, held to pair it with the other one
+    pub fn holds_pair_button(&self) -> bool {
+        match self.joycon_side() {
+            Some(JoyConSide::Left) => self.holds(&[KeyCode::BTN_TL]),
+            Some(JoyConSide::Right) => self.holds(&[KeyCode::BTN_TR]),
+            None => false,
+        }
+    }
+    // A Joy-Con's SL and SR, held together to play it sideways
+    pub fn holds_sideways_buttons(&self) -> bool {
+        match self.joycon_side() {
+            Some(JoyConSide::Left) => self.holds(&[KeyCode::BTN_TR, KeyCode::BTN_TR2]),
+            Some(JoyConSide::Right) => self.holds(&[KeyCode::BTN_TL, KeyCode::BTN_TL2]),
+            None => false,
+        }
+    }
+    pub fn held_buttons(&self) -> Vec<VirtualButton> {
+        let Ok(keys) = self.dev.get_key_state() else {
+            return Vec::new();
+        };
+        keys.iter().filter_map(VirtualButton::from_key).collect()
+    }
+    // Every button pressed since the last call, in order, plus repeats of a held direction
+    pub fn poll(&mut self) -> Vec<PadButton> {
+        let mut buttons = Vec::new();
+        let Ok(events) = self.dev.fetch_events() else {
+            self.repeat_direction(&mut buttons);
+            return buttons;
+        };
+        let events: Vec<InputEvent> = events.collect();
+        // Left Joy-Cons have no B or Start, so Capture and Minus stand in for them, as when
+        // one is played sideways
+        let left_joycon = self.joycon_side() == Some(JoyConSide::Left);
+        for event in events {
+            let pressed = match event.destructure() {
+                EventSummary::Key(_, KeyCode::BTN_Z, 1) if left_joycon => Some(PadButton::BBtn),
+                EventSummary::Key(_, KeyCode::BTN_SELECT, 1) if left_joycon => {
+                    Some(PadButton::StartBtn)
+                }
+                EventSummary::Key(_, KeyCode::BTN_SOUTH, 1) => Some(PadButton::ABtn),
+                EventSummary::Key(_, KeyCode::BTN_EAST, 1) => Some(PadButton::BBtn),
+                EventSummary::Key(_, KeyCode::BTN_NORTH, 1) => Some(PadButton::XBtn),
+                EventSummary::Key(_, KeyCode::BTN_WEST, 1) => Some(PadButton::YBtn),
+                EventSummary::Key(_, KeyCode::BTN_START, 1) => Some(PadButton::StartBtn),
+                EventSummary::Key(_, KeyCode::BTN_SELECT, 1) => Some(PadButton::SelectBtn),
+                EventSummary::Key(_, KeyCode::BTN_TL, 1) => Some(PadButton::LBtn),
+                EventSummary::Key(_, KeyCode::BTN_TR, 1) => Some(PadButton::RBtn),
+                // Digital triggers, on pads without analog ones
+                EventSummary::Key(_, KeyCode::BTN_TL2, value) => {
+                    self.nav.triggers.0 = value.signum() as f32;
+                    None
+                }
+                EventSummary::Key(_, KeyCode::BTN_TR2, value) => {
+                    self.nav.triggers.1 = value.signum() as f32;
+                    None
+                }
+                // D-pads reported as buttons, e.g. the left Joy-Con's
+                EventSummary::Key(_, KeyCode::BTN_DPAD_LEFT, value) => {
+                    self.set_hat(Some(-value.signum()), None)
+                }
+                EventSummary::Key(_, KeyCode::BTN_DPAD_RIGHT, value) => {
+                    self.set_hat(Some(value.signum()), None)
+                }
+                EventSummary::Key(_, KeyCode::BTN_DPAD_UP, value) => {
+                    self.set_hat(None, Some(-value.signum()))
+                }
+                EventSummary::Key(_, KeyCode::BTN_DPAD_DOWN, value) => {
+                    self.set_hat(None, Some(value.signum()))
+                }
+                EventSummary::AbsoluteAxis(_, AbsoluteAxisCode::ABS_HAT0X, value) => {
+                    self.set_hat(Some(value.signum()), None)
+                }
+                EventSummary::AbsoluteAxis(_, AbsoluteAxisCode::ABS_HAT0Y, value) => {
+                    self.set_hat(None, Some(value.signum()))
+                }
+                EventSummary::AbsoluteAxis(_, axis, value) => {
+                    let Some(&(min, max)) = self.ranges.get(&axis) else {
+                        continue;
+                    };
+                    let position = normalize_axis(value, min, max);
+                    match axis {
+                        AbsoluteAxisCode::ABS_X => self.nav.stick.0 = position,
+                        AbsoluteAxisCode::ABS_Y => self.nav.stick.1 = position,
+                        AbsoluteAxisCode::ABS_Z | AbsoluteAxisCode::ABS_BRAKE => {
+                            self.nav.triggers.0 = (position + 1.0) / 2.0
+                        }
+                        AbsoluteAxisCode::ABS_RZ | AbsoluteAxisCode::ABS_GAS => {
+                            self.nav.triggers.1 = (position + 1.0) / 2.0
+                        }
+                        _ => {}
                     }
-                    _ => btn,
-                };
+                    None
+                }
+                _ => None,
+            };
+            buttons.extend(pressed);
+        }
+        self.repeat_direction(&mut buttons);
+        buttons
+    }
+
+    // Moves the d-pad and returns the direction it was pressed in, if any. Presses are reported
+    // straight away so a quick tap isn't lost if it's released within the same batch.
+    fn set_hat(&mut self, x: Option<i32>, y: Option<i32>) -> Option<PadButton> {
+        let pressed = match (x, y) {
+            (Some(-1), _) => Some(PadButton::Left),
+            (Some(1), _) => Some(PadButton::Right),
+            (_, Some(-1)) => Some(PadButton::Up),
+            (_, Some(1)) => Some(PadButton::Down),
+            _ => None,
+        };
+        if let Some(x) = x {
+            self.nav.hat.0 = x;
+        }
+        if let Some(y) = y {
+            self.nav.hat.1 = y;
+        }
+        if let Some(pressed) = pressed {
+            self.nav.held = Some((pressed, Instant::now() + REPEAT_DELAY));
+        }
+        pressed
+    }
+
+    // Reports a newly pushed stick direction, and repeats a held direction
+    fn repeat_direction(&mut self, buttons: &mut Vec<PadButton>) {
+        let now = Instant::now();
+        self.nav.held = match (self.nav.direction(), self.nav.held) {
+            (None, _) => None,
+            (Some(dir), Some((held, next))) if dir == held => match now >= next {
+                true => {
+                    buttons.push(dir);
+                    Some((dir, now + REPEAT_INTERVAL))
+                }
+                false => Some((held, next)),
+            },
+            (Some(dir), _) => {
+                buttons.push(dir);
+                Some((dir, now + REPEAT_DELAY))
             }
+        };
+    }
+
+    // How far to scroll, from -1 when the left trigger is fully pressed to 1 for the right
+    pub fn scroll(&self) -> f32 {
+        let scroll = self.nav.triggers.1 - self.nav.triggers.0;
+        // Triggers rarely rest exactly at 0
+        match scroll.abs() < 0.1 {
+            true => 0.0,
+            false => scroll,
         }
-        btn
     }
 }
 
 pub fn scan_evdev_gamepads(filter_steam: bool) -> Vec<Gamepad> {
     let mut pads: Vec<Gamepad> = Vec::new();
     for dev in evdev::enumerate() {
-        if filter_steam {
-            let vendor = dev.1.input_id().vendor();
-            if vendor == 0x28de {
-                continue;
-            }
-            if let Some(name) = dev.1.name() {
-                if name.contains("Steam Virtual Gamepad") {
-                    continue;
-                }
-            }
+        if let Some(pad) = gamepad_from(dev, filter_steam) {
+            pads.push(pad);
         }
-        let has_btn_south = dev
-            .1
+    }
+    pads
+}
+
+// Opens a single event node, e.g. one that was just plugged in, if it's a gamepad
+pub fn open_gamepad(path: &Path, filter_steam: bool) -> Option<Gamepad> {
+    let dev = Device::open(path).ok()?;
+    gamepad_from((path.to_path_buf(), dev), filter_steam)
+}
+
+fn gamepad_from(dev: (PathBuf, Device), filter_steam: bool) -> Option<Gamepad> {
+    // A running session's virtual pads, which forward pads that are already listed
+    if dev
+        .1
+        .physical_path()
+        .is_some_and(|phys| phys.starts_with("partydeck/"))
+    {
+        return None;
+    }
+    if filter_steam {
+        let vendor = dev.1.input_id().vendor();
+        if vendor == 0x28de {
+            return None;
+        }
+        if let Some(name) = dev.1.name()
+            && name.contains("Steam Virtual Gamepad")
+        {
+            return None;
+        }
+    }
+    // Motion sensors, e.g. a Joy-Con's, get a node of their own with the pad's ids
+    if dev.1.properties().contains(PropType::ACCELEROMETER) {
+        return None;
+    }
+    let has_key = |key: KeyCode| {
+        dev.1
             .supported_keys()
-            .map_or(false, |keys| keys.contains(KeyCode::BTN_SOUTH));
-        if has_btn_south {
-            if dev.1.set_nonblocking(true).is_err() {
-                println!("Failed to set non-blocking mode for {}", dev.0.display());
-                continue;
+            .is_some_and(|keys| keys.contains(key))
+    };
+    // Left Joy-Cons only have a d-pad
+    let is_left_joycon =
+        joycon_side(dev.1.input_id()) == Some(JoyConSide::Left) && has_key(KeyCode::BTN_DPAD_UP);
+    if !has_key(KeyCode::BTN_SOUTH) && !is_left_joycon {
+        return None;
+    }
+    if dev.1.set_nonblocking(true).is_err() {
+        println!("Failed to set non-blocking mode for {}", dev.0.display());
+        return None;
+    }
+    let ranges = dev
+        .1
+        .get_absinfo()
+        .map(|axes| {
+            axes.map(|(axis, info)| (axis, (info.minimum(), info.maximum())))
+                .collect()
+        })
+        .unwrap_or_default();
+    Some(Gamepad {
+        path: dev.0.to_str().unwrap().to_string(),
+        dev: dev.1,
+        ranges,
+        nav: NavState::default(),
+    })
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum KbmKind {
+    Keyboard,
+    Mouse,
+}
+
+pub enum KbmEvent {
+    // Any key other than Escape
+    Key,
+    Escape,
+    Click,
+}
+
+// A keyboard or mouse event node
+pub struct KbmDevice {
+    path: String,
+    dev: Device,
+    kind: KbmKind,
+}
+
+impl KbmDevice {
+    pub fn name(&self) -> &str {
+        self.dev.name().unwrap_or("")
+    }
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+    pub fn kind(&self) -> KbmKind {
+        self.kind
+    }
+
+    // Every join, leave or pairing event since the last call, in order
+    pub fn poll(&mut self) -> Vec<KbmEvent> {
+        let mut out = Vec::new();
+        if let Ok(events) = self.dev.fetch_events() {
+            for event in events {
+                let found = match (self.kind, event.destructure()) {
+                    (KbmKind::Keyboard, EventSummary::Key(_, KeyCode::KEY_ESC, 1)) => {
+                        KbmEvent::Escape
+                    }
+                    // Codes from BTN_MISC on are buttons, not keys
+                    (KbmKind::Keyboard, EventSummary::Key(_, key, 1)) if key.0 < 0x100 => {
+                        KbmEvent::Key
+                    }
+                    (KbmKind::Mouse, EventSummary::Key(_, KeyCode::BTN_LEFT, 1))
+                    | (KbmKind::Mouse, EventSummary::Key(_, KeyCode::BTN_RIGHT, 1)) => {
+                        KbmEvent::Click
+                    }
+                    _ => continue,
+                };
+                out.push(found);
             }
-            pads.push(Gamepad {
-                path: dev.0.to_str().unwrap().to_string(),
-                dev: dev.1,
-            });
         }
+        out
     }
-    pads
 }
 
-#[allow(dead_code)]
-pub fn scan_evdev_mice() -> Vec<Device> {
-    let mut mice: Vec<Device> = Vec::new();
+// Finds keyboards with letter keys, leaving out extra nodes like media keys, and mice
+pub fn scan_evdev_kbm() -> Vec<KbmDevice> {
+    let mut devices: Vec<KbmDevice> = Vec::new();
     for dev in evdev::enumerate() {
-        let has_btn_left = dev
-            .1
-            .supported_keys()
-            .map_or(false, |keys| keys.contains(KeyCode::BTN_LEFT));
-        if has_btn_left {
-            if dev.1.set_nonblocking(true).is_err() {
-                println!("Failed to set non-blocking mode for {}", dev.0.display());
-                continue;
-            }
-            mice.push(dev.1);
+        if let Some(device) = kbm_from(dev) {
+            devices.push(device);
         }
     }
-    mice
+    devices
+}
+
+pub fn open_kbm(path: &Path) -> Option<KbmDevice> {
+    let dev = Device::open(path).ok()?;
+    kbm_from((path.to_path_buf(), dev))
+}
+
+fn kbm_from(dev: (PathBuf, Device)) -> Option<KbmDevice> {
+    let keys = dev.1.supported_keys();
+    let has = |key: KeyCode| keys.is_some_and(|keys| keys.contains(key));
+    let kind = if has(KeyCode::KEY_A) && has(KeyCode::KEY_SPACE) && !has(KeyCode::BTN_SOUTH) {
+        KbmKind::Keyboard
+    } else if has(KeyCode::BTN_LEFT)
+        && dev
+            .1
+            .supported_relative_axes()
+            .is_some_and(|axes| axes.contains(RelativeAxisCode::REL_X))
+    {
+        KbmKind::Mouse
+    } else {
+        return None;
+    };
+    if dev.1.set_nonblocking(true).is_err() {
+        println!("Failed to set non-blocking mode for {}", dev.0.display());
+        return None;
+    }
+    Some(KbmDevice {
+        path: dev.0.to_str().unwrap().to_string(),
+        dev: dev.1,
+        kind,
+    })
 }
@@ -0,0 +1,237 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::OnceLock;
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub enum UpscaleFilter {
+    Linear,
+    Nearest,
+    Fsr,
+    Nis,
+    Pixel,
+}
+
+impl UpscaleFilter {
+    pub const ALL: [UpscaleFilter; 5] = [
+        UpscaleFilter::Linear,
+        UpscaleFilter::Nearest,
+        UpscaleFilter::Fsr,
+        UpscaleFilter::Nis,
+        UpscaleFilter::Pixel,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            UpscaleFilter::Linear => "Linear",
+            UpscaleFilter::Nearest => "Nearest",
+            UpscaleFilter::Fsr => "AMD FSR",
+            UpscaleFilter::Nis => "NVIDIA NIS",
+            UpscaleFilter::Pixel => "Pixel",
+        }
+    }
+
+    fn arg(&self) -> &'static str {
+        match self {
+            UpscaleFilter::Linear => "linear",
+            UpscaleFilter::Nearest => "nearest",
+            UpscaleFilter::Fsr => "fsr",
+            UpscaleFilter::Nis => "nis",
+            UpscaleFilter::Pixel => "pixel",
+        }
+    }
+
+    fn from_arg(s: &str) -> Option<UpscaleFilter> {
+        UpscaleFilter::ALL.into_iter().find(|f| f.arg() == s)
+    }
+
+    // Only FSR and NIS take a sharpness value
+    pub fn sharpens(&self) -> bool {
+        matches!(self, UpscaleFilter::Fsr | UpscaleFilter::Nis)
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct GamescopeOptions {
+    // Percentage of the instance's output resolution the game renders at
+    pub internal_scale: u32,
+    pub filter: UpscaleFilter,
+    // 0 is sharpest, 20 is softest
+    pub sharpness: u32,
+    // 0 = unlimited
+    pub fps_limit: u32,
+    pub hdr: bool,
+    pub mangoapp: bool,
+    pub extra_args: String,
+}
+
+impl Default for GamescopeOptions {
+    fn default() -> Self {
+        Self {
+            internal_scale: 100,
+            filter: UpscaleFilter::Linear,
+            sharpness: 5,
+            fps_limit: 0,
+            hdr: false,
+            mangoapp: false,
+            extra_args: String::new(),
+        }
+    }
+}
+
+// Settings a handler can override, read from its "gamescope.*" keys
+#[derive(Clone, Default)]
+pub struct GamescopeOverrides {
+    pub internal_scale: Option<u32>,
+    pub filter: Option<UpscaleFilter>,
+    pub sharpness: Option<u32>,
+    pub fps_limit: Option<u32>,
+    pub hdr: Option<bool>,
+    pub mangoapp: Option<bool>,
+    pub extra_args: Option<String>,
+}
+
+impl GamescopeOverrides {
+    pub fn from_json(json: &Value) -> GamescopeOverrides {
+        let uint = |key: &str| json[key].as_u64().map(|v| v as u32);
+        GamescopeOverrides {
+            internal_scale: uint("gamescope.internal_scale"),
+            filter: json["gamescope.filter"]
+                .as_str()
+                .and_then(UpscaleFilter::from_arg),
+            sharpness: uint("gamescope.sharpness"),
+            fps_limit: uint("gamescope.fps_limit"),
+            hdr: json["gamescope.hdr"].as_bool(),
+            mangoapp: json["gamescope.mangoapp"].as_bool(),
+            extra_args: json["gamescope.extra_args"].as_str().map(|s| s.to_string()),
+        }
+    }
+}
+
+impl GamescopeOptions {
+    pub fn with_overrides(&self, o: &GamescopeOverrides) -> GamescopeOptions {
+        GamescopeOptions {
+            internal_scale: o.internal_scale.unwrap_or(self.internal_scale),
+            filter: o.filter.unwrap_or(self.filter),
+            sharpness: o.sharpness.unwrap_or(self.sharpness),
+            fps_limit: o.fps_limit.unwrap_or(self.fps_limit),
+            hdr: o.hdr.unwrap_or(self.hdr),
+            mangoapp: o.mangoapp.unwrap_or(self.mangoapp),
+            extra_args: o.extra_args.clone().unwrap_or(self.extra_args.clone()),
+        }
+    }
+}
+
+// Output of "gamescope --help", empty if gamescope couldn't be run
+fn gamescope_help() -> &'static str {
+    static HELP: OnceLock<String> = OnceLock::new();
+    HELP.get_or_init(|| {
+        match std::process::Command::new("gamescope")
+            .arg("--help")
+            .output()
+        {
+            // gamescope prints its usage to stderr
+            Ok(out) => format!(
+                "{}{}",
+                String::from_utf8_lossy(&out.stdout),
+                String::from_utf8_lossy(&out.stderr)
+            ),
+            Err(_) => String::new(),
+        }
+    })
+}
+
+// Whether this gamescope build knows `flag`. Assumes it does if --help couldn't be read.
+fn supports(flag: &str) -> bool {
+    let help = gamescope_help();
+    help.is_empty()
+        || help
+            .split(|c: char| c.is_whitespace() || c == ',' || c == '=')
+            .any(|word| word == flag)
+}
+
+// Builds "gamescope [settings] --". Flags this gamescope build doesn't support are left out
+// with a warning, since gamescope refuses to start with unknown flags.
+pub fn gamescope_args(
+    width: u32,
+    height: u32,
+    sdl_backend: bool,
+    opts: &GamescopeOptions,
+    warnings: &mut Vec<String>,
+) -> Vec<String> {
+    let mut argv = vec![
+        "gamescope".to_string(),
+        "-W".to_string(),
+        width.to_string(),
+        "-H".to_string(),
+        height.to_string(),
+    ];
+    let mut push = |argv: &mut Vec<String>, flag: &str, args: Vec<String>| {
+        if supports(flag) {
+            argv.push(flag.to_string());
+            argv.extend(args);
+        } else {
+            warn_unsupported(warnings, flag);
+        }
+    };
+
+    if sdl_backend {
+        argv.push("--backend=sdl".to_string());
+    }
+    if opts.internal_scale > 0 && opts.internal_scale < 100 {
+        let scale = opts.internal_scale as f32 / 100.0;
+        push(
+            &mut argv,
+            "-w",
+            vec![((width as f32 * scale) as u32).to_string()],
+        );
+        push(
+            &mut argv,
+            "-h",
+            vec![((height as f32 * scale) as u32).to_string()],
+        );
+        if opts.filter != UpscaleFilter::Linear {
+            push(&mut argv, "-F", vec![opts.filter.arg().to_string()]);
+        }
+        if opts.filter.sharpens() {
+            push(
+                &mut argv,
+                "--sharpness",
+                vec![opts.sharpness.min(20).to_string()],
+            );
+        }
+    }
+    if opts.fps_limit > 0 {
+        push(&mut argv, "-r", vec![opts.fps_limit.to_string()]);
+    }
+    if opts.hdr {
+        push(&mut argv, "--hdr-enabled", vec![]);
+    }
+    if opts.mangoapp {
+        push(&mut argv, "--mangoapp", vec![]);
+    }
+
+    // Extra flags are passed through as-is; an unsupported flag is dropped along with its values
+    let mut skipping = false;
+    for arg in opts.extra_args.split_whitespace() {
+        if arg.starts_with('-') {
+            let flag = arg.split('=').next().unwrap_or(arg);
+            skipping = !supports(flag);
+            if skipping {
+                warn_unsupported(warnings, flag);
+            }
+        }
+        if !skipping {
+            argv.push(arg.to_string());
+        }
+    }
+    argv.push("--".to_string());
+    argv
+}
+
+fn warn_unsupported(warnings: &mut Vec<String>, flag: &str) {
+    let warning = format!("Your gamescope doesn't support {flag}, ignoring it.");
+    if !warnings.contains(&warning) {
+        warnings.push(warning);
+    }
+}
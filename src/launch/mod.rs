@@ -0,0 +1,805 @@
+mod gamescope;
+mod plan;
+mod resources;
+mod runner;
+mod wrappers;
+
+use gamescope::gamescope_args;
+pub use gamescope::{GamescopeOptions, GamescopeOverrides, UpscaleFilter};
+pub use plan::{
+    Bind, InstancePlan, LanPlan, LaunchPlan, Readiness, SetupStep, shell_quote, split_words,
+};
+use resources::resource_prefix;
+pub use resources::{CpuPinning, ResourceOptions};
+pub use runner::Runner;
+pub use wrappers::WRAPPER_SUGGESTIONS;
+use wrappers::wrapper_chain;
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use crate::app::PartyConfig;
+use crate::game::exec_uid;
+use crate::handler::*;
+use crate::input::*;
+use crate::paths::*;
+use crate::remap::PadMapping;
+use crate::util::{
+    AudioOutput, MUTED_SINK, PrefixStrategy, find_in_path, find_proton_build, find_runtime,
+    get_instance_resolution, get_rootpath_handler, get_screen_resolution, prefix_initialised,
+    prefix_path, prefix_template, scan_proton_builds, scan_runtimes, version_at_least,
+};
+
+const DEFAULT_READY_TIMEOUT: f64 = 30.0;
+// Private range for session LANs, instances get .11 and up
+const LAN_SUBNET: &str = "10.13.37";
+
+const RES_WARNING: &str = "Instance resolution is below 600p! The game may experience graphical issues or not run at all. Increase the resolution scale in settings if this happens.";
+
+// Gives every player a profile name, picking a random guest name for players without a profile
+pub fn resolve_profiles(players: &mut Vec<Player>, profiles: &Vec<String>) {
+    let mut guests = GUEST_NAMES.to_vec();
+    for player in players {
+        if player.profselection == 0 || player.profselection >= profiles.len() {
+            let i = fastrand::usize(..guests.len());
+            player.profname = format!(".{}", guests[i]);
+            guests.swap_remove(i);
+        } else {
+            player.profname = profiles[player.profselection].to_owned();
+        }
+    }
+}
+
+pub fn kwin_script_path(cfg: &PartyConfig) -> PathBuf {
+    if cfg.vertical_two_player {
+        PATH_RES.join("splitscreen_kwin.js")
+    } else {
+        PATH_RES.join("splitscreen_kwin_horizontal.js")
+    }
+}
+
+pub fn launch_from_handler(
+    h: &Handler,
+    all_pads: &[Gamepad],
+    all_kbm: &[KbmDevice],
+    players: &Vec<Player>,
+    cfg: &PartyConfig,
+) -> Result<LaunchPlan, Box<dyn std::error::Error>> {
+    let home = PATH_HOME.display();
+    let localshare = PATH_LOCAL_SHARE.display();
+    let party = PATH_PARTY.display();
+    let steam = PATH_STEAM.display();
+
+    let mut warnings = Vec::new();
+
+    let gamedir = match h.symlink_dir {
+        true => format!("{party}/gamesyms/{}", h.uid),
+        false => get_rootpath_handler(&h)?,
+    };
+
+    let mut setup = Vec::new();
+    for p in players {
+        setup.push(SetupStep::CreateProfile {
+            profile: p.profname.clone(),
+        });
+        setup.push(SetupStep::CreateGamesave {
+            profile: p.profname.clone(),
+            uid: h.uid.clone(),
+        });
+    }
+    if h.symlink_dir {
+        setup.push(SetupStep::BuildSymlinkDir {
+            uid: h.uid.clone(),
+            path: gamedir.clone(),
+        });
+    }
+
+    // Instance command: "[limits] gamescope [settings] -- bwrap [binds] [wrappers] [runtime] [exec] [args]"
+    let mut env = BTreeMap::new();
+    env.insert("SDL_JOYSTICK_HIDAPI".to_string(), "0".to_string());
+    env.insert("ENABLE_GAMESCOPE_WSI".to_string(), "0".to_string());
+    env.insert("PROTON_DISABLE_HIDRAW".to_string(), "1".to_string());
+
+    if cfg.force_sdl && !h.win {
+        let path_sdl = match h.is32bit {
+            true => "/ubuntu12_32/steam-runtime/usr/lib/i386-linux-gnu/libSDL2-2.0.so.0",
+            false => "/ubuntu12_32/steam-runtime/usr/lib/x86_64-linux-gnu/libSDL2-2.0.so.0",
+        };
+        env.insert("SDL_DYNAMIC_API".to_string(), format!("{steam}/{path_sdl}"));
+    }
+    let runner = Runner::from_handler(h)?;
+    let mut protonpath = String::new();
+    if h.win {
+        runner.check()?;
+        if runner.uses_proton() {
+            protonpath = resolve_proton(&h.proton, &h.proton_min, cfg, &mut warnings)?;
+        }
+        env.extend(runner.env(&protonpath));
+        if cfg.prefix_strategy == PrefixStrategy::Shared {
+            env.insert("WINEPREFIX".to_string(), format!("{party}/pfx"));
+        }
+        if !h.dll_overrides.is_empty() {
+            let mut overrides = String::new();
+            for dll in &h.dll_overrides {
+                overrides.push_str(&format!("{dll},"));
+            }
+            overrides.push_str("=n,b");
+            env.insert("WINEDLLOVERRIDES".to_string(), overrides);
+        }
+        if h.coldclient {
+            env.insert("PROTON_DISABLE_LSTEAMCLIENT".to_string(), "1".to_string());
+        }
+    }
+
+    let exec = &h.exec.as_str();
+    // Windows games are started through their runner instead of a runtime
+    let runtime = match h.win || h.runtime.is_empty() {
+        true => String::new(),
+        false => match find_runtime(&scan_runtimes(), &h.runtime) {
+            Some(runtime) => runtime.entry_point.to_string_lossy().to_string(),
+            None => return Err(format!("Steam Linux Runtime ({}) not found", h.runtime).into()),
+        },
+    };
+
+    // The symlink folder is only built during setup, so look in the game's root folder until then
+    let path_exec = match h.symlink_dir && !PathBuf::from(&gamedir).exists() {
+        true => PathBuf::from(get_rootpath_handler(&h)?).join(exec),
+        false => PathBuf::from(&gamedir).join(exec),
+    };
+    if !path_exec.exists() {
+        return Err(format!("Executable ({exec}) not found").into());
+    }
+
+    let (screen_width, screen_height) = get_screen_resolution();
+    let scale_factor = cfg.render_scale as f32 / 100.0;
+    let width = (screen_width as f32 * scale_factor) as u32;
+    let height = (screen_height as f32 * scale_factor) as u32;
+    let gamescope = cfg.gamescope.with_overrides(&h.gamescope);
+    let network = lan_plan(cfg, &mut warnings)?;
+    let mut mappings = pad_mappings(cfg, all_pads, players);
+    let joycons = uses_joycons(all_pads, players);
+    let virtual_pads = check_virtual_pads(cfg, &mut mappings, joycons, &mut warnings)?;
+
+    let mut instances = Vec::new();
+    for (i, p) in players.iter().enumerate() {
+        let path_prof = &format!("{party}/profiles/{}", p.profname.as_str());
+        let path_save = &format!("{path_prof}/saves/{}", h.uid.as_str());
+
+        let (gsc_width, gsc_height) = get_instance_resolution(
+            players.len(),
+            i,
+            width,
+            height,
+            cfg.vertical_two_player,
+        );
+
+        if gsc_height < 600 && !warnings.iter().any(|w| w == RES_WARNING) {
+            warnings.push(RES_WARNING.to_string());
+        }
+
+        let mut argv = resource_prefix(&cfg.resources, i, players.len(), &mut warnings)?;
+        argv.extend(gamescope_args(
+            gsc_width,
+            gsc_height,
+            cfg.gamescope_sdl_backend,
+            &gamescope,
+            &mut warnings,
+        ));
+
+        // Bind player profile directories to the game's directories
+        let mut binds = Vec::new();
+
+        let path_goldberg = h.path_goldberg.as_str();
+        if !path_goldberg.is_empty() {
+            binds.push(Bind {
+                src: format!("{path_prof}/steam"),
+                dest: format!("{gamedir}/{path_goldberg}/goldbergsave"),
+            });
+        }
+        let mut instance_env = BTreeMap::new();
+        audio_env(&p.audio, &mut setup, &mut instance_env);
+        if h.win {
+            let prefix = instance_prefix(
+                cfg,
+                i,
+                &p.profname,
+                &runner,
+                &protonpath,
+                &mut setup,
+                &mut instance_env,
+            );
+            let path_windata = format!("{prefix}/drive_c/users/{}", runner.windows_user());
+            if h.win_unique_appdata {
+                binds.push(Bind {
+                    src: format!("{path_save}/_AppData"),
+                    dest: format!("{path_windata}/AppData"),
+                });
+            }
+            if h.win_unique_documents {
+                binds.push(Bind {
+                    src: format!("{path_save}/_Documents"),
+                    dest: format!("{path_windata}/Documents"),
+                });
+            }
+        } else {
+            if h.linux_unique_localshare {
+                binds.push(Bind {
+                    src: format!("{path_save}/_share"),
+                    dest: format!("{localshare}"),
+                });
+            }
+            if h.linux_unique_config {
+                binds.push(Bind {
+                    src: format!("{path_save}/_config"),
+                    dest: format!("{home}/.config"),
+                });
+            }
+        }
+        for subdir in &h.game_unique_paths {
+            binds.push(Bind {
+                src: format!("{path_save}/{subdir}"),
+                dest: format!("{gamedir}/{subdir}"),
+            });
+        }
+        // Mask out any gamepads, keyboards and mice that aren't this player's
+        binds.extend(input_masks(all_pads, all_kbm, p, virtual_pads));
+
+        push_bwrap_args(&mut argv, &binds);
+        // Handler args used to be run through a shell, so entries like "-foo bar" are still
+        // split into separate arguments
+        let args: Vec<String> = h
+            .args
+            .iter()
+            .flat_map(|arg| match arg.as_str() {
+                "$GAMEDIR" => vec![gamedir.clone()],
+                "$PROFILE" => vec![p.profname.clone()],
+                "$WIDTH" => vec![gsc_width.to_string()],
+                "$HEIGHT" => vec![gsc_height.to_string()],
+                "$WIDTHXHEIGHT" => vec![format!("{gsc_width}x{gsc_height}")],
+                _ => split_words(arg),
+            })
+            .collect();
+        argv.extend(wrapper_chain(cfg, &h.uid, &h.wrappers, i)?);
+        if h.win {
+            argv.extend(runner.command(&format!("{gamedir}/{exec}"), &args));
+        } else {
+            if !runtime.is_empty() {
+                argv.push(runtime.clone());
+            }
+            argv.push(format!("{gamedir}/{exec}"));
+            argv.extend(args);
+        }
+
+        instances.push(InstancePlan {
+            player: i,
+            profile: p.profname.clone(),
+            pads: p
+                .pad_indices()
+                .iter()
+                .filter_map(|i| all_pads.get(*i))
+                .map(|pad| pad.path().to_string())
+                .collect(),
+            keyboard: kbm_path(all_kbm, p, KbmKind::Keyboard),
+            mouse: kbm_path(all_kbm, p, KbmKind::Mouse),
+            width: gsc_width,
+            height: gsc_height,
+            env: instance_env,
+            binds,
+            argv,
+            ready: handler_readiness(h, cfg, &gamedir, &p.profname)?,
+            address: lan_address(&network, i),
+            mapping: mappings[i].take(),
+        });
+    }
+
+    Ok(LaunchPlan {
+        game: h.display().to_string(),
+        uid: h.uid.clone(),
+        win: h.win,
+        gamedir,
+        env,
+        setup,
+        instances,
+        ready_timeout: h.ready_timeout.unwrap_or(DEFAULT_READY_TIMEOUT) as f32,
+        kwin_script: kwin_script_path(cfg),
+        network,
+        virtual_pads,
+        warnings,
+    })
+}
+
+pub fn launch_executable(
+    exec_path: &PathBuf,
+    all_pads: &[Gamepad],
+    all_kbm: &[KbmDevice],
+    players: &Vec<Player>,
+    cfg: &PartyConfig,
+) -> Result<LaunchPlan, Box<dyn std::error::Error>> {
+    let exec = exec_path.to_string_lossy();
+
+    if !exec_path.exists() {
+        return Err(format!("Executable ({exec}) not found").into());
+    }
+
+    let party = PATH_PARTY.display();
+
+    let mut warnings = Vec::new();
+
+    let win = if exec_path.extension().unwrap_or_default() == "exe" {
+        true
+    } else {
+        false
+    };
+
+    let game = exec_path
+        .file_name()
+        .map(|f| f.to_string_lossy().to_string())
+        .unwrap_or_else(|| exec.to_string());
+    let uid = exec_uid(&game);
+    let runner = cfg.exec_runners.get(&uid).cloned().unwrap_or_default();
+
+    let gamedir = exec_path.parent().unwrap().to_string_lossy().to_string();
+
+    // Instance command: "[limits] gamescope [settings] -- bwrap [binds] [wrappers] [runner] [exec]"
+    let mut env = BTreeMap::new();
+    env.insert("SDL_JOYSTICK_HIDAPI".to_string(), "0".to_string());
+    env.insert("ENABLE_GAMESCOPE_WSI".to_string(), "0".to_string());
+
+    let mut protonpath = String::new();
+    if win {
+        runner.check()?;
+        if runner.uses_proton() {
+            protonpath = resolve_proton("", "", cfg, &mut warnings)?;
+        }
+        env.extend(runner.env(&protonpath));
+        if cfg.prefix_strategy == PrefixStrategy::Shared {
+            env.insert("WINEPREFIX".to_string(), format!("{party}/pfx"));
+        }
+    }
+
+    let (screen_width, screen_height) = get_screen_resolution();
+    let scale_factor = cfg.render_scale as f32 / 100.0;
+    let width = (screen_width as f32 * scale_factor) as u32;
+    let height = (screen_height as f32 * scale_factor) as u32;
+
+    let network = lan_plan(cfg, &mut warnings)?;
+    let mut mappings = pad_mappings(cfg, all_pads, players);
+    let joycons = uses_joycons(all_pads, players);
+    let virtual_pads = check_virtual_pads(cfg, &mut mappings, joycons, &mut warnings)?;
+
+    let mut setup = Vec::new();
+    let mut instances = Vec::new();
+    for (i, p) in players.iter().enumerate() {
+        let (gsc_width, gsc_height) = get_instance_resolution(
+            players.len(),
+            i,
+            width,
+            height,
+            cfg.vertical_two_player,
+        );
+
+        if gsc_height < 600 && !warnings.iter().any(|w| w == RES_WARNING) {
+            warnings.push(RES_WARNING.to_string());
+        }
+
+        let mut argv = resource_prefix(&cfg.resources, i, players.len(), &mut warnings)?;
+        argv.extend(gamescope_args(
+            gsc_width,
+            gsc_height,
+            true,
+            &cfg.gamescope,
+            &mut warnings,
+        ));
+
+        let mut instance_env = BTreeMap::new();
+        audio_env(&p.audio, &mut setup, &mut instance_env);
+        if win {
+            instance_prefix(
+                cfg,
+                i,
+                &p.profname,
+                &runner,
+                &protonpath,
+                &mut setup,
+                &mut instance_env,
+            );
+        }
+
+        // Mask out any gamepads, keyboards and mice that aren't this player's
+        let binds = input_masks(all_pads, all_kbm, p, virtual_pads);
+
+        push_bwrap_args(&mut argv, &binds);
+        argv.extend(wrapper_chain(cfg, &uid, &[], i)?);
+        match win {
+            true => argv.extend(runner.command(&exec, &[])),
+            false => argv.push(exec.to_string()),
+        }
+
+        instances.push(InstancePlan {
+            player: i,
+            profile: format!("Player {}", i + 1),
+            pads: p
+                .pad_indices()
+                .iter()
+                .filter_map(|i| all_pads.get(*i))
+                .map(|pad| pad.path().to_string())
+                .collect(),
+            keyboard: kbm_path(all_kbm, p, KbmKind::Keyboard),
+            mouse: kbm_path(all_kbm, p, KbmKind::Mouse),
+            width: gsc_width,
+            height: gsc_height,
+            env: instance_env,
+            binds,
+            argv,
+            ready: default_readiness(win, cfg),
+            address: lan_address(&network, i),
+            mapping: mappings[i].take(),
+        });
+    }
+
+    Ok(LaunchPlan {
+        uid,
+        game,
+        win,
+        gamedir,
+        env,
+        setup,
+        instances,
+        ready_timeout: DEFAULT_READY_TIMEOUT as f32,
+        kwin_script: kwin_script_path(cfg),
+        network,
+        virtual_pads,
+        warnings,
+    })
+}
+
+// Proton games have needed a ~5 second buffer in-between launches, likely because of
+// concurrent wineserver startup in the shared prefix. Handlers can pick a better strategy.
+fn default_readiness(win: bool, cfg: &PartyConfig) -> Readiness {
+    match win {
+        true if cfg.prefix_strategy == PrefixStrategy::Shared => Readiness::Delay { seconds: 6.0 },
+        // Separate prefixes each get their own wineserver
+        true => Readiness::Delay { seconds: 1.0 },
+        false => Readiness::Delay { seconds: 0.01 },
+    }
+}
+
+// Picks the Proton build for a launch: the handler's preferred build if it's installed,
+// otherwise the one from settings. Fails if the build is missing or older than `min`.
+fn resolve_proton(
+    preferred: &str,
+    min: &str,
+    cfg: &PartyConfig,
+    warnings: &mut Vec<String>,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let builds = scan_proton_builds(&cfg.proton_extra_dirs);
+    let selected = cfg.proton_version.as_str();
+
+    let mut build = find_proton_build(&builds, preferred);
+    if build.is_none() {
+        if !preferred.is_empty() {
+            warnings.push(format!(
+                "This game prefers Proton \"{preferred}\", which isn't installed."
+            ));
+        }
+        build = if selected.is_empty() || selected == "GE-Proton" {
+            // Prefer an installed GE-Proton over letting UMU look for the latest one online
+            builds.iter().find(|b| b.name.starts_with("GE-Proton"))
+        } else {
+            find_proton_build(&builds, selected)
+        };
+    }
+
+    let Some(build) = build else {
+        if selected.contains('/') {
+            return Err(format!("Proton build not found at {selected}").into());
+        }
+        let name = if selected.is_empty() {
+            "GE-Proton"
+        } else {
+            selected
+        };
+        if !min.is_empty() && !version_at_least(name, min) {
+            return Err(
+                format!("This game needs Proton {min} or newer, but {name} is selected").into(),
+            );
+        }
+        warnings.push(format!(
+            "Proton \"{name}\" isn't installed, UMU will try to download it. This needs an internet connection."
+        ));
+        return Ok(name.to_string());
+    };
+
+    if !min.is_empty() && !version_at_least(&build.version, min) {
+        return Err(format!(
+            "This game needs Proton {min} or newer, but {} is selected",
+            build.display()
+        )
+        .into());
+    }
+    Ok(build.path.to_string_lossy().to_string())
+}
+
+// Resolves the Wine prefix of player slot `slot`, adding the setup steps that create it
+// and its WINEPREFIX to the instance's env. Returns the prefix path.
+fn instance_prefix(
+    cfg: &PartyConfig,
+    slot: usize,
+    profile: &str,
+    runner: &Runner,
+    protonpath: &str,
+    setup: &mut Vec<SetupStep>,
+    env: &mut BTreeMap<String, String>,
+) -> String {
+    let key = runner.template_key(protonpath);
+    let prefix = prefix_path(cfg.prefix_strategy, slot, profile, key.as_deref());
+    let prefix_str = prefix.to_string_lossy().to_string();
+    if cfg.prefix_strategy == PrefixStrategy::Shared {
+        return prefix_str;
+    }
+
+    // Templates are kept per runner and Proton build so each prefix starts out with the Wine
+    // version that will run it. A custom command's Wine is unknown, so it creates its own.
+    if let Some(key) = key
+        && !prefix.exists()
+    {
+        let template = prefix_template(&key);
+        let template_str = template.to_string_lossy().to_string();
+        if !prefix_initialised(&template)
+            && !setup.iter().any(
+                |s| matches!(s, SetupStep::InitPrefixTemplate { path, .. } if *path == template_str),
+            )
+        {
+            setup.push(SetupStep::InitPrefixTemplate {
+                path: template_str.clone(),
+                argv: runner.command("wineboot", &["-u".to_string()]),
+                env: runner.env(protonpath),
+            });
+        }
+        setup.push(SetupStep::ClonePrefix {
+            from: template_str,
+            to: prefix_str.clone(),
+        });
+    }
+    env.insert("WINEPREFIX".to_string(), prefix_str.clone());
+    prefix_str
+}
+
+// Checks the tools a virtual LAN needs if network isolation is enabled
+fn lan_plan(
+    cfg: &PartyConfig,
+    warnings: &mut Vec<String>,
+) -> Result<Option<LanPlan>, Box<dyn std::error::Error>> {
+    if !cfg.network_isolation {
+        return Ok(None);
+    }
+    for cmd in ["unshare", "nsenter", "ip"] {
+        if find_in_path(cmd).is_none() {
+            return Err(format!("Network isolation needs {cmd}, which isn't installed").into());
+        }
+    }
+    let uplink = ["slirp4netns", "nft", "sysctl"]
+        .iter()
+        .all(|cmd| find_in_path(cmd).is_some());
+    if !uplink {
+        warnings.push("slirp4netns or nft isn't installed, so instances can only reach each other and not the internet.".to_string());
+    }
+    Ok(Some(LanPlan {
+        subnet: LAN_SUBNET.to_string(),
+        uplink,
+    }))
+}
+
+// Virtual pads need write access to /dev/uinput, usually given by a udev rule.
+// They're also used whenever a player's pad has remaps or stick shaping to apply, or is a
+// Joy-Con, to pair or turn it.
+fn check_virtual_pads(
+    cfg: &PartyConfig,
+    mappings: &mut [Option<PadMapping>],
+    joycons: bool,
+    warnings: &mut Vec<String>,
+) -> Result<bool, Box<dyn std::error::Error>> {
+    let remapped = mappings.iter().any(|m| m.is_some());
+    if !cfg.virtual_pads && !remapped && !joycons {
+        return Ok(false);
+    }
+    if unsafe { libc::access(c"/dev/uinput".as_ptr(), libc::W_OK) } != 0 {
+        if cfg.virtual_pads {
+            return Err("Virtual controllers need write access to /dev/uinput. Add a udev rule giving your user access, or turn off virtual controllers in the settings.".into());
+        }
+        if remapped {
+            warnings.push("Button remaps and stick settings need write access to /dev/uinput, so they aren't applied. Add a udev rule giving your user access.".to_string());
+        }
+        if joycons {
+            warnings.push("Joy-Cons need write access to /dev/uinput to be paired or held sideways, so each is used as it is. Add a udev rule giving your user access.".to_string());
+        }
+        mappings.iter_mut().for_each(|m| *m = None);
+        return Ok(false);
+    }
+    Ok(true)
+}
+
+fn uses_joycons(all_pads: &[Gamepad], players: &[Player]) -> bool {
+    players
+        .iter()
+        .flat_map(|p| p.pad_indices())
+        .filter_map(|i| all_pads.get(i))
+        .any(|pad| pad.joycon_side().is_some())
+}
+
+// Each player's remaps and stick shaping, by their profile or else their pad's model
+fn pad_mappings(
+    cfg: &PartyConfig,
+    all_pads: &[Gamepad],
+    players: &[Player],
+) -> Vec<Option<PadMapping>> {
+    players
+        .iter()
+        .map(|p| {
+            let pad = all_pads.get(p.pad_index()?)?;
+            cfg.pad_mapping(&p.profname, &pad.model()).cloned()
+        })
+        .collect()
+}
+
+fn lan_address(network: &Option<LanPlan>, slot: usize) -> Option<String> {
+    network
+        .as_ref()
+        .map(|lan| format!("{}.{}", lan.subnet, 11 + slot))
+}
+
+// Routes an instance's audio to the player's output. PULSE_SINK covers PulseAudio clients
+// (including pipewire-pulse), PIPEWIRE_NODE covers native PipeWire clients.
+fn audio_env(output: &AudioOutput, setup: &mut Vec<SetupStep>, env: &mut BTreeMap<String, String>) {
+    let sink = match output {
+        AudioOutput::Default => return,
+        AudioOutput::Sink(name) => name.clone(),
+        AudioOutput::Muted => {
+            if !setup
+                .iter()
+                .any(|s| matches!(s, SetupStep::CreateNullSink { .. }))
+            {
+                setup.push(SetupStep::CreateNullSink {
+                    name: MUTED_SINK.to_string(),
+                });
+            }
+            MUTED_SINK.to_string()
+        }
+    };
+    env.insert("PULSE_SINK".to_string(), sink.clone());
+    env.insert("PIPEWIRE_NODE".to_string(), sink);
+}
+
+fn handler_readiness(
+    h: &Handler,
+    cfg: &PartyConfig,
+    gamedir: &str,
+    profile: &str,
+) -> Result<Readiness, Box<dyn std::error::Error>> {
+    let target = h
+        .ready_target
+        .replace("$GAMEDIR", gamedir)
+        .replace("$PROFILE", profile);
+    let ready = match h.ready.as_str() {
+        "" => match h.ready_delay {
+            Some(seconds) => Readiness::Delay {
+                seconds: seconds as f32,
+            },
+            None => default_readiness(h.win, cfg),
+        },
+        "window" => Readiness::Window,
+        "delay" => Readiness::Delay {
+            seconds: h.ready_delay.unwrap_or(6.0) as f32,
+        },
+        "process" | "file" | "log" if target.is_empty() => {
+            return Err(
+                format!("Handler readiness \"{}\" needs game.ready_target", h.ready).into(),
+            );
+        }
+        // Process names are truncated to 15 characters in /proc/<pid>/comm
+        "process" => Readiness::Process {
+            name: target.chars().take(15).collect(),
+        },
+        "file" => Readiness::File { path: target },
+        "log" => Readiness::LogLine { text: target },
+        other => return Err(format!("Unknown handler readiness \"{other}\"").into()),
+    };
+    Ok(ready)
+}
+
+// Adds binds to an argv built by push_bwrap_args, for nodes only known once the session runs
+pub fn add_bwrap_binds(argv: &mut Vec<String>, binds: &[Bind]) {
+    // After bwrap's fixed arguments, so the binds go on top of the "/" bind
+    let Some(pos) = argv
+        .iter()
+        .position(|arg| arg == "bwrap")
+        .map(|i| i + BWRAP_FIXED_ARGS.len())
+    else {
+        return;
+    };
+    for (n, bind) in binds.iter().enumerate() {
+        argv.splice(
+            pos + n * 3..pos + n * 3,
+            ["--bind".to_string(), bind.src.clone(), bind.dest.clone()],
+        );
+    }
+}
+
+const BWRAP_FIXED_ARGS: [&str; 7] = [
+    "bwrap",
+    "--die-with-parent",
+    "--dev-bind",
+    "/",
+    "/",
+    "--tmpfs",
+    "/tmp",
+];
+
+fn push_bwrap_args(argv: &mut Vec<String>, binds: &Vec<Bind>) {
+    for arg in BWRAP_FIXED_ARGS {
+        argv.push(arg.to_string());
+    }
+    for bind in binds {
+        argv.push("--bind".to_string());
+        argv.push(bind.src.clone());
+        argv.push(bind.dest.clone());
+    }
+}
+
+// With virtual pads, the player's own pads are hidden too since their virtual pad stands in for them
+fn input_masks(
+    all_pads: &[Gamepad],
+    all_kbm: &[KbmDevice],
+    p: &Player,
+    virtual_pads: bool,
+) -> Vec<Bind> {
+    let mut binds: Vec<Bind> = Vec::new();
+    let own_pads: Vec<&str> = match virtual_pads {
+        true => Vec::new(),
+        false => p
+            .pad_indices()
+            .iter()
+            .filter_map(|i| all_pads.get(*i))
+            .map(|pad| pad.path())
+            .collect(),
+    };
+    for (i, pad) in all_pads.iter().enumerate() {
+        if p.pad_indices().contains(&i) && !virtual_pads {
+            continue;
+        }
+        // Hide the whole controller, except the player's own pads if they share a parent with it
+        for node in pad.device_nodes() {
+            if own_pads.contains(&node.as_str()) || binds.iter().any(|b| b.dest == node) {
+                continue;
+            }
+            binds.push(Bind {
+                src: "/dev/null".to_string(),
+                dest: node,
+            });
+        }
+    }
+    for (i, dev) in all_kbm.iter().enumerate() {
+        if p.has_kbm(i) {
+            continue;
+        }
+        binds.push(Bind {
+            src: "/dev/null".to_string(),
+            dest: dev.path().to_string(),
+        });
+    }
+    binds
+}
+
+fn kbm_path(all_kbm: &[KbmDevice], p: &Player, kind: KbmKind) -> Option<String> {
+    all_kbm
+        .iter()
+        .enumerate()
+        .find(|(i, dev)| dev.kind() == kind && p.has_kbm(*i))
+        .map(|(_, dev)| dev.path().to_string())
+}
+
+static GUEST_NAMES: [&str; 21] = [
+    "Blinky", "Pinky", "Inky", "Clyde", "Beatrice", "Battler", "Ellie", "Joel", "Leon", "Ada",
+    "Madeline", "Theo", "Yokatta", "Wyrm", "Brodiee", "Supreme", "Conk", "Gort", "Lich", "Smores",
+    "Canary",
+];
@@ -0,0 +1,311 @@
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::path::{Path, PathBuf};
+
+use crate::handler::{Handler, create_symlink_folder};
+use crate::remap::PadMapping;
+use crate::util::{
+    clone_prefix, create_gamesave, create_null_sink, create_profile, init_prefix,
+    prefix_initialised,
+};
+
+// Everything a launch resolves to before any process is started.
+// Built by launch_from_handler/launch_executable, executed by the app or exported for debugging.
+#[derive(Serialize, Clone)]
+pub struct LaunchPlan {
+    pub game: String,
+    // Handler uid, or the sanitized file name for executables
+    pub uid: String,
+    pub win: bool,
+    pub gamedir: String,
+    pub env: BTreeMap<String, String>,
+    pub setup: Vec<SetupStep>,
+    pub instances: Vec<InstancePlan>,
+    // Seconds to wait for an instance to become ready before moving on to the next one anyway
+    pub ready_timeout: f32,
+    pub kwin_script: PathBuf,
+    // Private virtual LAN the instances are connected to, each in its own network namespace
+    pub network: Option<LanPlan>,
+    // Each player's pad is forwarded through a virtual pad created when the session starts
+    pub virtual_pads: bool,
+    pub warnings: Vec<String>,
+}
+
+#[derive(Serialize, Clone)]
+pub struct LanPlan {
+    // First three octets of the LAN's /24, the hub is .1
+    pub subnet: String,
+    // Internet access through slirp4netns
+    pub uplink: bool,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(tag = "step", rename_all = "snake_case")]
+pub enum SetupStep {
+    CreateProfile {
+        profile: String,
+    },
+    CreateGamesave {
+        profile: String,
+        uid: String,
+    },
+    BuildSymlinkDir {
+        uid: String,
+        path: String,
+    },
+    InitPrefixTemplate {
+        path: String,
+        argv: Vec<String>,
+        env: BTreeMap<String, String>,
+    },
+    ClonePrefix {
+        from: String,
+        to: String,
+    },
+    // An audio sink that discards its input, for muted players. Removed when the session ends.
+    CreateNullSink {
+        name: String,
+    },
+}
+
+#[derive(Serialize, Clone)]
+pub struct InstancePlan {
+    pub player: usize,
+    pub profile: String,
+    // Two for a pair of Joy-Cons
+    pub pads: Vec<String>,
+    pub keyboard: Option<String>,
+    pub mouse: Option<String>,
+    pub width: u32,
+    pub height: u32,
+    // Applied on top of the plan-wide env for this instance only
+    pub env: BTreeMap<String, String>,
+    pub binds: Vec<Bind>,
+    pub argv: Vec<String>,
+    // When the next instance may be started
+    pub ready: Readiness,
+    // Address on the session's virtual LAN
+    pub address: Option<String>,
+    // Remaps and stick shaping applied by the player's virtual pad
+    pub mapping: Option<PadMapping>,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Readiness {
+    // The instance's gamescope window has been mapped
+    Window,
+    // A process with this name runs in the instance's process tree
+    Process { name: String },
+    // A file exists
+    File { path: String },
+    // The instance's log contains a line with this text
+    LogLine { text: String },
+    // A fixed number of seconds has passed
+    Delay { seconds: f32 },
+}
+
+#[derive(Serialize, Clone)]
+pub struct Bind {
+    pub src: String,
+    pub dest: String,
+}
+
+impl LaunchPlan {
+    pub fn to_json(&self) -> Result<String, Box<dyn Error>> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    // Renders the plan as a standalone script that starts the same instances PartyDeck would.
+    // Setup steps are listed as comments since they're performed by PartyDeck itself.
+    pub fn to_shell_script(&self) -> String {
+        let mut out = String::new();
+        out.push_str("#!/bin/sh\n");
+        out.push_str(&format!("# PartyDeck launch plan for {}\n", self.game));
+        for warning in &self.warnings {
+            out.push_str(&format!("# WARNING: {warning}\n"));
+        }
+        if !self.setup.is_empty() {
+            out.push_str("#\n# Setup performed by PartyDeck before launching:\n");
+            for step in &self.setup {
+                out.push_str(&format!("#   {}\n", step.describe()));
+            }
+        }
+        out.push('\n');
+
+        for (key, value) in &self.env {
+            out.push_str(&format!("export {key}={}\n", shell_quote(value)));
+        }
+        out.push_str(&format!("cd {}\n\n", shell_quote(&self.gamedir)));
+
+        for (i, instance) in self.instances.iter().enumerate() {
+            out.push_str(&format!(
+                "# Player {}: {} ({}x{})\n",
+                instance.player + 1,
+                instance.profile,
+                instance.width,
+                instance.height
+            ));
+            if self.virtual_pads && !instance.pads.is_empty() {
+                out.push_str(
+                    "# PartyDeck gives this player a virtual pad fed by their own, which this script can't create\n",
+                );
+            }
+            if let Some(address) = &instance.address {
+                out.push_str(&format!(
+                    "# PartyDeck runs this in its own network namespace at {address}\n"
+                ));
+            }
+            let mut cmd = String::new();
+            if !instance.env.is_empty() {
+                cmd.push_str("env ");
+                for (key, value) in &instance.env {
+                    cmd.push_str(&format!("{key}={} ", shell_quote(value)));
+                }
+            }
+            cmd.push_str(
+                &instance
+                    .argv
+                    .iter()
+                    .map(|arg| shell_quote(arg))
+                    .collect::<Vec<_>>()
+                    .join(" "),
+            );
+            out.push_str(&format!("{cmd} &\n"));
+            if i < self.instances.len() - 1 {
+                out.push_str(&instance.ready.to_shell(self.ready_timeout));
+            }
+            out.push('\n');
+        }
+        out.push_str("wait\n");
+        out
+    }
+}
+
+impl Readiness {
+    pub fn describe(&self) -> String {
+        match self {
+            Readiness::Window => "its gamescope window".to_string(),
+            Readiness::Process { name } => format!("process \"{name}\""),
+            Readiness::File { path } => format!("file {path}"),
+            Readiness::LogLine { text } => format!("log line \"{text}\""),
+            Readiness::Delay { seconds } => format!("{seconds} seconds"),
+        }
+    }
+
+    // Shell equivalent used by exported scripts. Windows and log lines can't be observed
+    // from a plain script, so those wait for the timeout instead.
+    fn to_shell(&self, timeout: f32) -> String {
+        let tries = (timeout * 2.0).ceil() as u32;
+        match self {
+            Readiness::Delay { seconds } => format!("sleep {seconds}\n"),
+            Readiness::Process { name } => format!(
+                "i=0; until pgrep -x {} >/dev/null || [ $i -ge {tries} ]; do sleep 0.5; i=$((i+1)); done\n",
+                shell_quote(name)
+            ),
+            Readiness::File { path } => format!(
+                "i=0; until [ -e {} ] || [ $i -ge {tries} ]; do sleep 0.5; i=$((i+1)); done\n",
+                shell_quote(path)
+            ),
+            Readiness::Window | Readiness::LogLine { .. } => format!(
+                "# waits for {} when launched by PartyDeck\nsleep {timeout}\n",
+                self.describe()
+            ),
+        }
+    }
+}
+
+impl SetupStep {
+    // Creates the profile, game save, symlink folder, Wine prefix or audio sink of this step
+    pub fn run(&self, h: Option<&Handler>) -> Result<(), Box<dyn Error>> {
+        match self {
+            SetupStep::CreateProfile { profile } => create_profile(profile)?,
+            SetupStep::CreateGamesave { profile, .. } => {
+                if let Some(h) = h {
+                    create_gamesave(profile, h)?;
+                }
+            }
+            SetupStep::BuildSymlinkDir { .. } => {
+                if let Some(h) = h {
+                    create_symlink_folder(h)?;
+                }
+            }
+            SetupStep::InitPrefixTemplate { path, argv, env } => {
+                if !prefix_initialised(Path::new(path)) {
+                    init_prefix(Path::new(path), argv, env)?;
+                }
+            }
+            SetupStep::ClonePrefix { from, to } => {
+                if !Path::new(to).exists() {
+                    clone_prefix(Path::new(from), Path::new(to))?;
+                }
+            }
+            SetupStep::CreateNullSink { name } => create_null_sink(name)?,
+        }
+        Ok(())
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            SetupStep::CreateProfile { profile } => format!("create profile \"{profile}\""),
+            SetupStep::CreateGamesave { profile, uid } => {
+                format!("create game save for {uid} in profile \"{profile}\"")
+            }
+            SetupStep::BuildSymlinkDir { uid, path } => {
+                format!("build symlink folder for {uid} at {path}")
+            }
+            SetupStep::InitPrefixTemplate { path, argv, .. } => {
+                format!(
+                    "initialise template Wine prefix {path} with {}",
+                    argv.join(" ")
+                )
+            }
+            SetupStep::ClonePrefix { from, to } => {
+                format!("clone Wine prefix {from} to {to}")
+            }
+            SetupStep::CreateNullSink { name } => format!("create muted audio sink {name}"),
+        }
+    }
+}
+
+// Splits `s` into words like a shell would, honouring quotes and backslashes without
+// expanding anything
+pub fn split_words(s: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut word: Option<String> = None;
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => words.extend(word.take()),
+            '\'' => {
+                let word = word.get_or_insert_with(String::new);
+                word.extend(chars.by_ref().take_while(|c| *c != '\''));
+            }
+            '"' => {
+                let word = word.get_or_insert_with(String::new);
+                while let Some(c) = chars.next() {
+                    match c {
+                        '"' => break,
+                        '\\' => word.extend(chars.next()),
+                        c => word.push(c),
+                    }
+                }
+            }
+            '\\' => word.get_or_insert_with(String::new).extend(chars.next()),
+            c => word.get_or_insert_with(String::new).push(c),
+        }
+    }
+    words.extend(word);
+    words
+}
+
+pub fn shell_quote(s: &str) -> String {
+    if !s.is_empty()
+        && s.chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./=:,+@%".contains(c))
+    {
+        return s.to_string();
+    }
+    format!("'{}'", s.replace('\'', "'\\''"))
+}
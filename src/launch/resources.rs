@@ -0,0 +1,172 @@
+use serde::{Deserialize, Serialize};
+
+use crate::util::find_in_path;
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub enum CpuPinning {
+    Off,
+    // Every instance gets an equal share of whole cores
+    Even,
+    // Each player slot uses the CPU list from ResourceOptions::cpu_map
+    Custom,
+}
+
+impl CpuPinning {
+    pub fn label(&self) -> &'static str {
+        match self {
+            CpuPinning::Off => "Off",
+            CpuPinning::Even => "Split cores evenly",
+            CpuPinning::Custom => "Custom",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct ResourceOptions {
+    pub cpu_pinning: CpuPinning,
+    // CPU lists in taskset format ("0-1,4") per player slot
+    pub cpu_map: Vec<String>,
+    // 0 leaves the priority alone
+    pub nice: i32,
+    pub ionice: bool,
+    // Best-effort I/O priority, 0 is highest and 7 lowest
+    pub ionice_level: u32,
+    // Per-instance memory limit in MiB, 0 = unlimited
+    pub memory_limit: u32,
+}
+
+impl Default for ResourceOptions {
+    fn default() -> Self {
+        Self {
+            cpu_pinning: CpuPinning::Off,
+            cpu_map: vec![String::new(); 4],
+            nice: 0,
+            ionice: false,
+            ionice_level: 4,
+            memory_limit: 0,
+        }
+    }
+}
+
+// Builds the commands that start player slot `slot` of `count` with its CPU affinity,
+// priorities and memory limit. They all exec the next command, so the instance keeps its pid.
+pub fn resource_prefix(
+    opts: &ResourceOptions,
+    slot: usize,
+    count: usize,
+    warnings: &mut Vec<String>,
+) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let mut argv = Vec::new();
+
+    if opts.memory_limit > 0 && available("systemd-run", warnings) {
+        argv.extend([
+            "systemd-run".to_string(),
+            "--user".to_string(),
+            "--scope".to_string(),
+            "--quiet".to_string(),
+            "-p".to_string(),
+            format!("MemoryMax={}M", opts.memory_limit),
+            "--".to_string(),
+        ]);
+    }
+
+    let cpus = match opts.cpu_pinning {
+        CpuPinning::Off => String::new(),
+        CpuPinning::Even => even_split(&cpu_order(), slot, count),
+        CpuPinning::Custom => {
+            let cpus = opts.cpu_map.get(slot).cloned().unwrap_or_default();
+            if !cpus
+                .chars()
+                .all(|c| c.is_ascii_digit() || c == ',' || c == '-')
+            {
+                return Err(format!("Invalid CPU list for player {}: {cpus}", slot + 1).into());
+            }
+            cpus
+        }
+    };
+    if !cpus.is_empty() && available("taskset", warnings) {
+        argv.extend(["taskset".to_string(), "-c".to_string(), cpus]);
+    }
+
+    if opts.nice != 0 && available("nice", warnings) {
+        argv.extend(["nice".to_string(), "-n".to_string(), opts.nice.to_string()]);
+    }
+    if opts.ionice && available("ionice", warnings) {
+        argv.extend([
+            "ionice".to_string(),
+            "-c".to_string(),
+            "2".to_string(),
+            "-n".to_string(),
+            opts.ionice_level.min(7).to_string(),
+        ]);
+    }
+    Ok(argv)
+}
+
+fn available(cmd: &str, warnings: &mut Vec<String>) -> bool {
+    if find_in_path(cmd).is_some() {
+        return true;
+    }
+    let warning = format!("{cmd} isn't installed, its performance settings are ignored.");
+    if !warnings.contains(&warning) {
+        warnings.push(warning);
+    }
+    false
+}
+
+// Online CPUs ordered so SMT siblings are next to each other, so contiguous chunks are whole cores
+fn cpu_order() -> Vec<u32> {
+    let count = std::thread::available_parallelism()
+        .map(|n| n.get() as u32)
+        .unwrap_or(1);
+    let mut out = Vec::new();
+    for cpu in 0..count {
+        if out.contains(&cpu) {
+            continue;
+        }
+        let siblings = std::fs::read_to_string(format!(
+            "/sys/devices/system/cpu/cpu{cpu}/topology/thread_siblings_list"
+        ))
+        .map(|list| parse_cpu_list(&list))
+        .unwrap_or_default();
+        if siblings.is_empty() {
+            out.push(cpu);
+        }
+        for sibling in siblings {
+            if sibling < count && !out.contains(&sibling) {
+                out.push(sibling);
+            }
+        }
+    }
+    out
+}
+
+fn parse_cpu_list(list: &str) -> Vec<u32> {
+    let mut out = Vec::new();
+    for part in list.trim().split(',') {
+        match part.split_once('-') {
+            Some((start, end)) => {
+                if let (Ok(start), Ok(end)) = (start.parse::<u32>(), end.parse::<u32>()) {
+                    out.extend(start..=end);
+                }
+            }
+            None => out.extend(part.parse::<u32>()),
+        }
+    }
+    out
+}
+
+// Slot `slot`'s share of `cpus`. With more instances than CPUs, instances share single CPUs.
+fn even_split(cpus: &[u32], slot: usize, count: usize) -> String {
+    if cpus.is_empty() || count == 0 {
+        return String::new();
+    }
+    let per = (cpus.len() / count).max(1);
+    let start = (slot * per) % cpus.len();
+    cpus[start..(start + per).min(cpus.len())]
+        .iter()
+        .map(|cpu| cpu.to_string())
+        .collect::<Vec<_>>()
+        .join(",")
+}
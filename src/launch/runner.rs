@@ -0,0 +1,156 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use crate::handler::Handler;
+use crate::paths::*;
+use crate::util::{SanitizePath, find_in_path};
+
+// What Windows executables are run through
+#[derive(Serialize, Deserialize, Clone, PartialEq, Default)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Runner {
+    // res/umu-run with a Proton build
+    #[default]
+    Umu,
+    // A system or custom Wine binary, e.g. "wine" or a Wine-GE build's bin/wine
+    Wine {
+        binary: String,
+    },
+    // A user-defined command. $EXE is replaced by the executable and $ARGS by its arguments,
+    // either is appended if the template doesn't contain it.
+    Custom {
+        template: String,
+    },
+}
+
+impl Runner {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Runner::Umu => "UMU (Proton)",
+            Runner::Wine { .. } => "Wine",
+            Runner::Custom { .. } => "Custom command",
+        }
+    }
+
+    // Reads game.runner, game.wine_path and game.runner_cmd
+    pub fn from_handler(h: &Handler) -> Result<Runner, Box<dyn std::error::Error>> {
+        let runner = match h.runner.as_str() {
+            "" | "umu" | "proton" => Runner::Umu,
+            "wine" => Runner::Wine {
+                binary: match h.wine_path.is_empty() {
+                    true => "wine".to_string(),
+                    false => h.wine_path.clone(),
+                },
+            },
+            "custom" if h.runner_cmd.is_empty() => {
+                return Err("Handler runner \"custom\" needs game.runner_cmd".into());
+            }
+            "custom" => Runner::Custom {
+                template: h.runner_cmd.clone(),
+            },
+            other => return Err(format!("Unknown handler runner \"{other}\"").into()),
+        };
+        Ok(runner)
+    }
+
+    pub fn uses_proton(&self) -> bool {
+        matches!(self, Runner::Umu)
+    }
+
+    // Makes sure the runner's binary can be found before anything is started
+    pub fn check(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let binary = match self {
+            Runner::Umu => PATH_RES.join("umu-run").to_string_lossy().to_string(),
+            Runner::Wine { binary } => binary.clone(),
+            Runner::Custom { template } => template
+                .split_whitespace()
+                .next()
+                .unwrap_or_default()
+                .to_string(),
+        };
+        if binary.is_empty() {
+            return Err(format!("{} runner has no command set", self.label()).into());
+        }
+        if find_in_path(&binary).is_none() {
+            return Err(format!("{} runner not found: {binary}", self.label()).into());
+        }
+        Ok(())
+    }
+
+    // Env shared by every instance run through this runner
+    pub fn env(&self, protonpath: &str) -> BTreeMap<String, String> {
+        let mut env = BTreeMap::new();
+        if let Runner::Umu = self {
+            env.insert("PROTON_VERB".to_string(), "run".to_string());
+            env.insert("PROTONPATH".to_string(), protonpath.to_string());
+        }
+        env
+    }
+
+    // The command that runs `exec` with `args`
+    pub fn command(&self, exec: &str, args: &[String]) -> Vec<String> {
+        match self {
+            Runner::Umu => {
+                let mut argv = vec![PATH_RES.join("umu-run").to_string_lossy().to_string()];
+                argv.push(exec.to_string());
+                argv.extend_from_slice(args);
+                argv
+            }
+            Runner::Wine { binary } => {
+                let mut argv = vec![binary.clone(), exec.to_string()];
+                argv.extend_from_slice(args);
+                argv
+            }
+            Runner::Custom { template } => {
+                let mut argv = Vec::new();
+                let mut has_exec = false;
+                let mut has_args = false;
+                for token in template.split_whitespace() {
+                    match token {
+                        "$EXE" => {
+                            has_exec = true;
+                            argv.push(exec.to_string());
+                        }
+                        "$ARGS" => {
+                            has_args = true;
+                            argv.extend_from_slice(args);
+                        }
+                        _ => argv.push(token.to_string()),
+                    }
+                }
+                if !has_exec {
+                    argv.push(exec.to_string());
+                }
+                if !has_args {
+                    argv.extend_from_slice(args);
+                }
+                argv
+            }
+        }
+    }
+
+    // Name of the template prefix that prefixes run by this runner are cloned from,
+    // or None if the runner's Wine version isn't known
+    pub fn template_key(&self, protonpath: &str) -> Option<String> {
+        match self {
+            Runner::Umu => {
+                let build = Path::new(protonpath)
+                    .file_name()
+                    .map(|name| name.to_string_lossy().to_string())
+                    .unwrap_or_default();
+                Some(format!("proton-{}", build.sanitize_path()))
+            }
+            Runner::Wine { binary } => Some(format!("wine-{}", binary.sanitize_path())),
+            Runner::Custom { .. } => None,
+        }
+    }
+
+    // Proton always creates the user "steamuser", plain Wine uses the current user's name
+    pub fn windows_user(&self) -> String {
+        match self {
+            Runner::Umu => "steamuser".to_string(),
+            _ => std::env::var("USER").unwrap_or("steamuser".to_string()),
+        }
+    }
+}
@@ -0,0 +1,39 @@
+use crate::app::PartyConfig;
+use crate::util::find_in_path;
+
+// Common wrappers offered in the settings, any other command works too
+pub const WRAPPER_SUGGESTIONS: [&str; 4] =
+    ["gamemoderun", "mangohud", "obs-gamecapture", "strace -f"];
+
+// The wrappers for player slot `slot`, outermost first: global, then the handler's,
+// then the ones set for this game in settings, then the slot's own
+pub fn wrapper_chain(
+    cfg: &PartyConfig,
+    uid: &str,
+    handler_wrappers: &[String],
+    slot: usize,
+) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let mut argv = Vec::new();
+    let chain = cfg
+        .wrappers
+        .iter()
+        .chain(handler_wrappers)
+        .chain(cfg.game_wrappers.get(uid).into_iter().flatten())
+        .chain(cfg.slot_wrappers.get(slot).into_iter().flatten());
+    for wrapper in chain {
+        let mut words = wrapper.split_whitespace();
+        let Some(binary) = words.next() else {
+            continue;
+        };
+        if find_in_path(binary).is_none() {
+            return Err(format!(
+                "Wrapper \"{binary}\" (player {}) isn't installed. Install it or remove it from the wrapper settings.",
+                slot + 1
+            )
+            .into());
+        }
+        argv.push(binary.to_string());
+        argv.extend(words.map(|w| w.to_string()));
+    }
+    Ok(argv)
+}
@@ -1,9 +1,13 @@
 mod app;
+mod cli;
 mod game;
 mod handler;
+mod hotplug;
 mod input;
 mod launch;
 mod paths;
+mod remap;
+mod session;
 mod task;
 mod util;
 
@@ -81,6 +85,12 @@ fn main() -> eframe::Result {
     std::fs::create_dir_all(PATH_PARTY.join("profiles"))
         .expect("Failed to create profiles directory");
 
+    // Subcommands can run alongside the GUI, so they leave its guest profiles and temp files alone
+    let args: Vec<String> = std::env::args().collect();
+    if let Some(code) = cli::run(&args) {
+        std::process::exit(code);
+    }
+
     remove_guest_profiles().unwrap();
 
     if PATH_PARTY.join("tmp").exists() {
@@ -114,7 +124,7 @@ fn main() -> eframe::Result {
 
     println!("\n[PARTYDECK] started\n");
 
-    let fullscreen = std::env::args().any(|arg| arg == "--fullscreen");
+    let fullscreen = args.iter().any(|arg| arg == "--fullscreen");
 
     let (_, scrheight) = get_screen_resolution();
 
@@ -0,0 +1,167 @@
+use evdev::KeyCode;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+// The buttons of a virtual pad, in Xbox naming. X and Y use the codes xpad sends for them.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum VirtualButton {
+    A,
+    B,
+    X,
+    Y,
+    LB,
+    RB,
+    Back,
+    Start,
+    Guide,
+    LS,
+    RS,
+}
+
+impl VirtualButton {
+    pub const ALL: [VirtualButton; 11] = [
+        VirtualButton::A,
+        VirtualButton::B,
+        VirtualButton::X,
+        VirtualButton::Y,
+        VirtualButton::LB,
+        VirtualButton::RB,
+        VirtualButton::Back,
+        VirtualButton::Start,
+        VirtualButton::Guide,
+        VirtualButton::LS,
+        VirtualButton::RS,
+    ];
+
+    pub fn key(self) -> KeyCode {
+        match self {
+            VirtualButton::A => KeyCode::BTN_SOUTH,
+            VirtualButton::B => KeyCode::BTN_EAST,
+            VirtualButton::X => KeyCode::BTN_NORTH,
+            VirtualButton::Y => KeyCode::BTN_WEST,
+            VirtualButton::LB => KeyCode::BTN_TL,
+            VirtualButton::RB => KeyCode::BTN_TR,
+            VirtualButton::Back => KeyCode::BTN_SELECT,
+            VirtualButton::Start => KeyCode::BTN_START,
+            VirtualButton::Guide => KeyCode::BTN_MODE,
+            VirtualButton::LS => KeyCode::BTN_THUMBL,
+            VirtualButton::RS => KeyCode::BTN_THUMBR,
+        }
+    }
+
+    pub fn from_key(key: KeyCode) -> Option<VirtualButton> {
+        VirtualButton::ALL.into_iter().find(|b| b.key() == key)
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            VirtualButton::A => "A",
+            VirtualButton::B => "B",
+            VirtualButton::X => "X",
+            VirtualButton::Y => "Y",
+            VirtualButton::LB => "LB",
+            VirtualButton::RB => "RB",
+            VirtualButton::Back => "Back",
+            VirtualButton::Start => "Start",
+            VirtualButton::Guide => "Guide",
+            VirtualButton::LS => "Left stick",
+            VirtualButton::RS => "Right stick",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(default)]
+pub struct StickShape {
+    // Fraction of the stick's travel ignored around the centre, for sticks that drift
+    pub deadzone: f32,
+    // Response exponent: 1 is linear, higher gives finer control near the centre
+    pub curve: f32,
+    pub invert_x: bool,
+    pub invert_y: bool,
+}
+
+impl Default for StickShape {
+    fn default() -> Self {
+        Self {
+            deadzone: 0.0,
+            curve: 1.0,
+            invert_x: false,
+            invert_y: false,
+        }
+    }
+}
+
+impl StickShape {
+    // Takes and returns a stick position with both axes from -1 to 1.
+    // The deadzone is radial so diagonals aren't cut off, and the rest of the travel is
+    // stretched over the full range.
+    pub fn apply(&self, x: f32, y: f32) -> (f32, f32) {
+        let magnitude = x.hypot(y);
+        let deadzone = self.deadzone.clamp(0.0, 0.9);
+        let (mut x, mut y) = match magnitude <= deadzone {
+            true => (0.0, 0.0),
+            false => {
+                let shaped = ((magnitude - deadzone) / (1.0 - deadzone))
+                    .min(1.0)
+                    .powf(self.curve.max(0.1));
+                (x * shaped / magnitude, y * shaped / magnitude)
+            }
+        };
+        if self.invert_x {
+            x = -x;
+        }
+        if self.invert_y {
+            y = -y;
+        }
+        (x, y)
+    }
+}
+
+// How a player's pad is changed on its way to their virtual pad
+#[derive(Serialize, Deserialize, Clone, PartialEq, Default, Debug)]
+#[serde(default)]
+pub struct PadMapping {
+    // Button sent for each physical button that's remapped
+    pub buttons: BTreeMap<VirtualButton, VirtualButton>,
+    pub left_stick: StickShape,
+    pub right_stick: StickShape,
+}
+
+impl PadMapping {
+    pub fn button(&self, button: VirtualButton) -> VirtualButton {
+        self.buttons.get(&button).copied().unwrap_or(button)
+    }
+
+    pub fn set_button(&mut self, from: VirtualButton, to: VirtualButton) {
+        match from == to {
+            true => self.buttons.remove(&from),
+            false => self.buttons.insert(from, to),
+        };
+    }
+
+    // For pads with Nintendo's layout, so the buttons games show match the ones pressed
+    pub fn swap_face_buttons(&mut self) {
+        for (a, b) in [
+            (VirtualButton::A, VirtualButton::B),
+            (VirtualButton::X, VirtualButton::Y),
+        ] {
+            let (to_a, to_b) = (self.button(b), self.button(a));
+            self.set_button(a, to_a);
+            self.set_button(b, to_b);
+        }
+    }
+
+    pub fn is_default(&self) -> bool {
+        *self == PadMapping::default()
+    }
+}
+
+// Maps an axis value within its range to -1..1
+pub fn normalize_axis(value: i32, min: i32, max: i32) -> f32 {
+    if max <= min {
+        return 0.0;
+    }
+    let value = value.clamp(min, max) - min;
+    (value as f32 / (max - min) as f32) * 2.0 - 1.0
+}
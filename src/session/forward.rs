@@ -0,0 +1,472 @@
+use evdev::uinput::VirtualDevice;
+use evdev::{
+    AbsInfo, AbsoluteAxisCode, AttributeSet, BusType, Device, EventSummary, EventType, InputEvent,
+    InputId, KeyCode, UinputAbsSetup,
+};
+use std::collections::HashMap;
+use std::error::Error;
+use std::ffi::CString;
+use std::os::fd::AsRawFd;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use crate::input::{JoyConSide, scan_evdev_gamepads};
+use crate::remap::{PadMapping, VirtualButton, normalize_axis};
+use crate::util::{log_error, log_info};
+
+// Virtual pads pose as an Xbox 360 controller, which every game takes as an XInput pad
+const VIRTUAL_VENDOR: u16 = 0x045e;
+const VIRTUAL_PRODUCT: u16 = 0x028e;
+const VIRTUAL_VERSION: u16 = 0x0110;
+const STICK_MIN: i32 = -32768;
+const STICK_MAX: i32 = 32767;
+const TRIGGER_MAX: i32 = 255;
+const RECONNECT_INTERVAL: Duration = Duration::from_millis(500);
+// How long a read waits for events before checking whether to stop
+const READ_TIMEOUT_MS: i32 = 100;
+
+// A uinput gamepad for one player, fed with the events of their physical pad, or of both
+// Joy-Cons they play with. If a pad disconnects, the virtual pad stays and forwarding resumes
+// once it's back. Button remaps and stick shaping are applied on the way.
+pub struct PadForwarder {
+    // Device nodes of the virtual pad
+    nodes: Vec<String>,
+    connected: Arc<AtomicBool>,
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl PadForwarder {
+    pub fn start(
+        player: usize,
+        pad_paths: &[String],
+        mapping: PadMapping,
+    ) -> Result<PadForwarder, Box<dyn Error>> {
+        let pads = scan_evdev_gamepads(false);
+        let mut sources = Vec::new();
+        for path in pad_paths {
+            let pad = pads
+                .iter()
+                .find(|pad| pad.path() == path)
+                .ok_or_else(|| format!("{path} is no longer connected"))?;
+            let layout = match (pad.joycon_side(), pad_paths.len()) {
+                (Some(side), 1) => Layout::JoyConSideways(side),
+                (Some(side), _) => Layout::JoyConHalf(side),
+                (None, _) => Layout::Standard,
+            };
+            let mut dev = Device::open(path)?;
+            // Keeps the physical pad's events from reaching anything else, e.g. after a reconnect
+            dev.grab()?;
+            sources.push(Source::new(pad.identity(), layout, dev));
+        }
+
+        let mut virt = create_virtual_pad(player)?;
+        let syspath = virt.get_syspath()?;
+        let mut nodes = Vec::new();
+        for entry in std::fs::read_dir(syspath)?.flatten() {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if name.starts_with("event") || name.starts_with("js") {
+                nodes.push(format!("/dev/input/{name}"));
+            }
+        }
+        log_info(&format!(
+            "Forwarding {} to virtual pad {} for player {}",
+            pad_paths.join(" and "),
+            nodes.join(", "),
+            player + 1
+        ));
+
+        let connected = Arc::new(AtomicBool::new(true));
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread = {
+            let connected = connected.clone();
+            let stop = stop.clone();
+            std::thread::spawn(move || {
+                forward(virt, sources, mapping, player, &connected, &stop);
+            })
+        };
+        Ok(PadForwarder {
+            nodes,
+            connected,
+            stop,
+            thread: Some(thread),
+        })
+    }
+
+    pub fn nodes(&self) -> &[String] {
+        &self.nodes
+    }
+
+    pub fn connected(&self) -> bool {
+        self.connected.load(Ordering::Relaxed)
+    }
+}
+
+impl Drop for PadForwarder {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+fn create_virtual_pad(player: usize) -> Result<VirtualDevice, Box<dyn Error>> {
+    let mut keys = AttributeSet::<KeyCode>::new();
+    for button in VirtualButton::ALL {
+        keys.insert(button.key());
+    }
+    let stick = AbsInfo::new(0, STICK_MIN, STICK_MAX, 16, 128, 0);
+    let trigger = AbsInfo::new(0, 0, TRIGGER_MAX, 0, 0, 0);
+    let hat = AbsInfo::new(0, -1, 1, 0, 0, 0);
+    // The phys tells our virtual pads apart from real ones with the same ids
+    let phys = CString::new(format!("partydeck/player{}", player + 1))?;
+    let mut builder = VirtualDevice::builder()?
+        .name("Microsoft X-Box 360 pad")
+        .input_id(InputId::new(
+            BusType::BUS_USB,
+            VIRTUAL_VENDOR,
+            VIRTUAL_PRODUCT,
+            VIRTUAL_VERSION,
+        ))
+        .with_phys(&phys)?
+        .with_keys(&keys)?;
+    for (axis, info) in [
+        (AbsoluteAxisCode::ABS_X, stick),
+        (AbsoluteAxisCode::ABS_Y, stick),
+        (AbsoluteAxisCode::ABS_RX, stick),
+        (AbsoluteAxisCode::ABS_RY, stick),
+        (AbsoluteAxisCode::ABS_Z, trigger),
+        (AbsoluteAxisCode::ABS_RZ, trigger),
+        (AbsoluteAxisCode::ABS_HAT0X, hat),
+        (AbsoluteAxisCode::ABS_HAT0Y, hat),
+    ] {
+        builder = builder.with_absolute_axis(&UinputAbsSetup::new(axis, info))?;
+    }
+    Ok(builder.build()?)
+}
+
+// Where a physical pad's buttons and sticks go on the virtual pad
+#[derive(Clone, Copy)]
+enum Layout {
+    Standard,
+    // One of a pair of Joy-Cons, whose SL and SR are left out
+    JoyConHalf(JoyConSide),
+    // A single Joy-Con held sideways, its stick being the left stick
+    JoyConSideways(JoyConSide),
+}
+
+impl Layout {
+    // Moves a button to where it is on the virtual pad. hid-nintendo names buttons by their
+    // position when the Joy-Con is upright, so sideways they turn by a quarter.
+    fn key(self, code: KeyCode) -> Option<KeyCode> {
+        let button = match (self, code) {
+            (Layout::Standard, _) => return Some(code),
+            (Layout::JoyConHalf(JoyConSide::Left), KeyCode::BTN_TR | KeyCode::BTN_TR2) => {
+                return None;
+            }
+            (Layout::JoyConHalf(JoyConSide::Right), KeyCode::BTN_TL | KeyCode::BTN_TL2) => {
+                return None;
+            }
+            (Layout::JoyConHalf(_), _) => return Some(code),
+            (Layout::JoyConSideways(JoyConSide::Left), _) => match code {
+                KeyCode::BTN_DPAD_LEFT => VirtualButton::A,
+                KeyCode::BTN_DPAD_DOWN => VirtualButton::B,
+                KeyCode::BTN_DPAD_UP => VirtualButton::X,
+                KeyCode::BTN_DPAD_RIGHT => VirtualButton::Y,
+                // SL and SR
+                KeyCode::BTN_TR => VirtualButton::LB,
+                KeyCode::BTN_TR2 => VirtualButton::RB,
+                // Minus and Capture
+                KeyCode::BTN_SELECT => VirtualButton::Start,
+                KeyCode::BTN_Z => VirtualButton::Back,
+                KeyCode::BTN_THUMBL => VirtualButton::LS,
+                _ => return None,
+            },
+            (Layout::JoyConSideways(JoyConSide::Right), _) => match code {
+                KeyCode::BTN_EAST => VirtualButton::A,
+                KeyCode::BTN_NORTH => VirtualButton::B,
+                KeyCode::BTN_SOUTH => VirtualButton::X,
+                KeyCode::BTN_WEST => VirtualButton::Y,
+                // SL and SR
+                KeyCode::BTN_TL => VirtualButton::LB,
+                KeyCode::BTN_TL2 => VirtualButton::RB,
+                // Plus and Home
+                KeyCode::BTN_START => VirtualButton::Start,
+                KeyCode::BTN_MODE => VirtualButton::Guide,
+                KeyCode::BTN_THUMBR => VirtualButton::LS,
+                _ => return None,
+            },
+        };
+        Some(button.key())
+    }
+
+    // Turns a stick position the same way, returning which virtual stick it's for
+    fn stick(self, stick: usize, x: f32, y: f32) -> (usize, f32, f32) {
+        match self {
+            Layout::JoyConSideways(JoyConSide::Left) => (0, y, -x),
+            Layout::JoyConSideways(JoyConSide::Right) => (0, -y, x),
+            _ => (stick, x, y),
+        }
+    }
+}
+
+// A physical pad feeding the virtual pad, with the ranges of its axes.
+// `dev` is None while it's disconnected.
+struct Source {
+    identity: String,
+    layout: Layout,
+    dev: Option<Device>,
+    ranges: HashMap<AbsoluteAxisCode, (i32, i32)>,
+    analog_triggers: bool,
+    // Last position of each stick from -1 to 1, before shaping
+    sticks: [(f32, f32); 2],
+}
+
+impl Source {
+    fn new(identity: String, layout: Layout, dev: Device) -> Source {
+        let mut source = Source {
+            identity,
+            layout,
+            dev: None,
+            ranges: HashMap::new(),
+            analog_triggers: false,
+            sticks: [(0.0, 0.0); 2],
+        };
+        source.attach(dev);
+        source
+    }
+
+    fn attach(&mut self, dev: Device) {
+        self.ranges = dev
+            .get_absinfo()
+            .map(|axes| {
+                axes.map(|(axis, info)| (axis, (info.minimum(), info.maximum())))
+                    .collect()
+            })
+            .unwrap_or_default();
+        self.analog_triggers = [AbsoluteAxisCode::ABS_Z, AbsoluteAxisCode::ABS_BRAKE]
+            .iter()
+            .any(|axis| self.ranges.contains_key(axis));
+        self.sticks = [(0.0, 0.0); 2];
+        self.dev = Some(dev);
+    }
+
+    // Converts an event of the physical pad into the virtual pad's layout and ranges
+    fn translate(&mut self, event: InputEvent, mapping: &PadMapping, out: &mut Vec<InputEvent>) {
+        let key = |code: KeyCode, value: i32| InputEvent::new(EventType::KEY.0, code.0, value);
+        let abs = |axis: AbsoluteAxisCode, value: i32| {
+            InputEvent::new(EventType::ABSOLUTE.0, axis.0, value)
+        };
+        let event = match event.destructure() {
+            EventSummary::Key(_, code, value) => {
+                let Some(code) = self.layout.key(code) else {
+                    return;
+                };
+                match VirtualButton::from_key(code) {
+                    Some(button) => Some(key(mapping.button(button).key(), value)),
+                    None => match code {
+                        // Digital triggers, on pads that don't have analog ones
+                        KeyCode::BTN_TL2 if !self.analog_triggers => {
+                            Some(abs(AbsoluteAxisCode::ABS_Z, value.signum() * TRIGGER_MAX))
+                        }
+                        KeyCode::BTN_TR2 if !self.analog_triggers => {
+                            Some(abs(AbsoluteAxisCode::ABS_RZ, value.signum() * TRIGGER_MAX))
+                        }
+                        // D-pads reported as buttons
+                        KeyCode::BTN_DPAD_LEFT => {
+                            Some(abs(AbsoluteAxisCode::ABS_HAT0X, -value.signum()))
+                        }
+                        KeyCode::BTN_DPAD_RIGHT => {
+                            Some(abs(AbsoluteAxisCode::ABS_HAT0X, value.signum()))
+                        }
+                        KeyCode::BTN_DPAD_UP => {
+                            Some(abs(AbsoluteAxisCode::ABS_HAT0Y, -value.signum()))
+                        }
+                        KeyCode::BTN_DPAD_DOWN => {
+                            Some(abs(AbsoluteAxisCode::ABS_HAT0Y, value.signum()))
+                        }
+                        _ => None,
+                    },
+                }
+            }
+            EventSummary::AbsoluteAxis(_, axis, value) => {
+                let Some((min, max)) = self.ranges.get(&axis).copied() else {
+                    return;
+                };
+                match axis {
+                    AbsoluteAxisCode::ABS_X
+                    | AbsoluteAxisCode::ABS_Y
+                    | AbsoluteAxisCode::ABS_RX
+                    | AbsoluteAxisCode::ABS_RY => {
+                        let stick = match axis {
+                            AbsoluteAxisCode::ABS_X | AbsoluteAxisCode::ABS_Y => 0,
+                            _ => 1,
+                        };
+                        let position = normalize_axis(value, min, max);
+                        match axis {
+                            AbsoluteAxisCode::ABS_X | AbsoluteAxisCode::ABS_RX => {
+                                self.sticks[stick].0 = position
+                            }
+                            _ => self.sticks[stick].1 = position,
+                        }
+                        // Both axes of the stick are sent, since a deadzone or curve on one
+                        // depends on the other
+                        let (x, y) = self.sticks[stick];
+                        let (stick, x, y) = self.layout.stick(stick, x, y);
+                        let (shape, x_axis, y_axis) = match stick {
+                            0 => (
+                                &mapping.left_stick,
+                                AbsoluteAxisCode::ABS_X,
+                                AbsoluteAxisCode::ABS_Y,
+                            ),
+                            _ => (
+                                &mapping.right_stick,
+                                AbsoluteAxisCode::ABS_RX,
+                                AbsoluteAxisCode::ABS_RY,
+                            ),
+                        };
+                        let (x, y) = shape.apply(x, y);
+                        out.push(abs(x_axis, (x * STICK_MAX as f32).round() as i32));
+                        out.push(abs(y_axis, (y * STICK_MAX as f32).round() as i32));
+                        None
+                    }
+                    // Some drivers, e.g. xpadneo, report triggers as brake and gas
+                    AbsoluteAxisCode::ABS_Z | AbsoluteAxisCode::ABS_BRAKE => Some(abs(
+                        AbsoluteAxisCode::ABS_Z,
+                        scale(value, min, max, 0, TRIGGER_MAX),
+                    )),
+                    AbsoluteAxisCode::ABS_RZ | AbsoluteAxisCode::ABS_GAS => Some(abs(
+                        AbsoluteAxisCode::ABS_RZ,
+                        scale(value, min, max, 0, TRIGGER_MAX),
+                    )),
+                    AbsoluteAxisCode::ABS_HAT0X | AbsoluteAxisCode::ABS_HAT0Y => {
+                        Some(abs(axis, value.signum()))
+                    }
+                    _ => None,
+                }
+            }
+            _ => None,
+        };
+        out.extend(event);
+    }
+}
+
+fn scale(value: i32, min: i32, max: i32, out_min: i32, out_max: i32) -> i32 {
+    if max <= min {
+        return out_min;
+    }
+    let value = value.clamp(min, max) as i64;
+    (out_min as i64 + (value - min as i64) * (out_max - out_min) as i64 / (max - min) as i64) as i32
+}
+
+// Events putting the virtual pad back at rest, so nothing stays held while the pad is away
+fn neutral_events() -> Vec<InputEvent> {
+    let mut events: Vec<InputEvent> = VirtualButton::ALL
+        .iter()
+        .map(|button| InputEvent::new(EventType::KEY.0, button.key().0, 0))
+        .collect();
+    for axis in [
+        AbsoluteAxisCode::ABS_X,
+        AbsoluteAxisCode::ABS_Y,
+        AbsoluteAxisCode::ABS_RX,
+        AbsoluteAxisCode::ABS_RY,
+        AbsoluteAxisCode::ABS_Z,
+        AbsoluteAxisCode::ABS_RZ,
+        AbsoluteAxisCode::ABS_HAT0X,
+        AbsoluteAxisCode::ABS_HAT0Y,
+    ] {
+        events.push(InputEvent::new(EventType::ABSOLUTE.0, axis.0, 0));
+    }
+    events
+}
+
+fn forward(
+    mut virt: VirtualDevice,
+    mut sources: Vec<Source>,
+    mapping: PadMapping,
+    player: usize,
+    connected: &AtomicBool,
+    stop: &AtomicBool,
+) {
+    let mut last_scan = Instant::now();
+    while !stop.load(Ordering::Relaxed) {
+        if sources.iter().any(|src| src.dev.is_none()) && last_scan.elapsed() >= RECONNECT_INTERVAL
+        {
+            // The pad's node may have changed, so look it up by identity
+            last_scan = Instant::now();
+            let pads = scan_evdev_gamepads(false);
+            for src in sources.iter_mut().filter(|src| src.dev.is_none()) {
+                if let Some(pad) = pads.iter().find(|pad| pad.identity() == src.identity)
+                    && let Ok(mut dev) = Device::open(pad.path())
+                {
+                    let _ = dev.grab();
+                    log_info(&format!(
+                        "Player {}'s pad is back at {}",
+                        player + 1,
+                        pad.path()
+                    ));
+                    src.attach(dev);
+                }
+            }
+            connected.store(
+                sources.iter().all(|src| src.dev.is_some()),
+                Ordering::Relaxed,
+            );
+        }
+
+        // poll() skips negative fds, i.e. disconnected pads
+        let mut pollfds: Vec<libc::pollfd> = sources
+            .iter()
+            .map(|src| libc::pollfd {
+                fd: src.dev.as_ref().map_or(-1, |dev| dev.as_raw_fd()),
+                events: libc::POLLIN,
+                revents: 0,
+            })
+            .collect();
+        let ready = unsafe {
+            libc::poll(
+                pollfds.as_mut_ptr(),
+                pollfds.len() as libc::nfds_t,
+                READ_TIMEOUT_MS,
+            )
+        };
+        if ready <= 0 {
+            continue;
+        }
+
+        let mut out = Vec::new();
+        for (src, pollfd) in sources.iter_mut().zip(&pollfds) {
+            if pollfd.revents == 0 {
+                continue;
+            }
+            let Some(dev) = &mut src.dev else {
+                continue;
+            };
+            let events = match dev.fetch_events().map(|events| events.collect::<Vec<_>>()) {
+                Ok(events) => events,
+                Err(err) if err.kind() == std::io::ErrorKind::WouldBlock => continue,
+                Err(err) => {
+                    log_error(&format!("Player {}'s pad disconnected: {err}", player + 1));
+                    src.dev = None;
+                    connected.store(false, Ordering::Relaxed);
+                    out.extend(neutral_events());
+                    continue;
+                }
+            };
+            for event in events {
+                src.translate(event, &mapping, &mut out);
+            }
+        }
+        if !out.is_empty()
+            && let Err(err) = virt.emit(&out)
+        {
+            log_error(&format!(
+                "Couldn't write to player {}'s virtual pad: {err}",
+                player + 1
+            ));
+        }
+    }
+}
@@ -0,0 +1,47 @@
+use std::error::Error;
+use std::path::{Path, PathBuf};
+
+use crate::paths::PATH_PARTY;
+use crate::util::{kwin_dbus_start_script, kwin_dbus_unload_script};
+
+// KWin script name of this process's session, so a GUI and a CLI session don't replace or
+// unload each other's layout
+pub fn session_script_name() -> String {
+    format!("splitscreen-{}", std::process::id())
+}
+
+// Writes a copy of the splitscreen script that pins each gamescope window to its player's slot,
+// so a relaunched instance lands in the same tile, then (re)loads it into KWin
+pub fn load_session_script(
+    base: &Path,
+    player_count: usize,
+    pids: &[Option<u32>],
+    reload: bool,
+) -> Result<(), Box<dyn Error>> {
+    let script = write_session_script(base, player_count, pids)?;
+    if reload {
+        kwin_dbus_unload_script(&session_script_name())?;
+    }
+    kwin_dbus_start_script(script, &session_script_name())
+}
+
+fn write_session_script(
+    base: &Path,
+    player_count: usize,
+    pids: &[Option<u32>],
+) -> Result<PathBuf, Box<dyn Error>> {
+    let mut out = String::from("var slotByPid = {};\n");
+    for (slot, pid) in pids.iter().enumerate() {
+        if let Some(pid) = pid {
+            out.push_str(&format!("slotByPid[{pid}] = {slot};\n"));
+        }
+    }
+    out.push_str(&format!("var playerCount = {player_count};\n\n"));
+    out.push_str(&std::fs::read_to_string(base)?);
+
+    let dir = PATH_PARTY.join("tmp");
+    std::fs::create_dir_all(&dir)?;
+    let path = dir.join(format!("{}.js", session_script_name()));
+    std::fs::write(&path, out)?;
+    Ok(path)
+}
@@ -0,0 +1,86 @@
+use chrono::Local;
+use std::error::Error;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+use crate::paths::PATH_PARTY;
+
+// Timestamp starting every session directory's name, always TIMESTAMP_LEN characters long
+const TIMESTAMP_FORMAT: &str = "%Y%m%d-%H%M%S";
+const TIMESTAMP_LEN: usize = 15;
+
+// Creates PATH_PARTY/logs/<timestamp>-<uid>/ for a new session
+pub fn create_session_dir(uid: &str) -> Result<PathBuf, Box<dyn Error>> {
+    let name = format!("{}-{uid}", Local::now().format(TIMESTAMP_FORMAT));
+    let dir = PATH_PARTY.join("logs").join(name);
+    std::fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
+
+// Removes the oldest session log directories so that at most `keep` remain
+pub fn prune_session_dirs(keep: usize) {
+    let mut dirs = list_session_dirs();
+    if dirs.len() <= keep {
+        return;
+    }
+    let excess = dirs.len() - keep;
+    for dir in dirs.drain(..excess) {
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
+
+// The newest session log directory for a game, if any
+pub fn last_session_dir(uid: &str) -> Option<PathBuf> {
+    // The uid is everything after the timestamp, so uids ending in this one don't match
+    list_session_dirs().into_iter().rev().find(|dir| {
+        dir.file_name()
+            .and_then(|n| n.to_str())
+            .and_then(|n| n.get(TIMESTAMP_LEN + 1..))
+            .is_some_and(|n| n == uid)
+    })
+}
+
+// Log files in a session directory, sorted by name
+pub fn session_log_files(dir: &Path) -> Vec<PathBuf> {
+    let mut out = Vec::new();
+    for entry in walkdir::WalkDir::new(dir)
+        .min_depth(1)
+        .into_iter()
+        .flatten()
+    {
+        if entry.file_type().is_file() {
+            out.push(entry.into_path());
+        }
+    }
+    out.sort();
+    out
+}
+
+// Reads at most the last `max_bytes` of a log file
+pub fn read_log_tail(path: &Path, max_bytes: u64) -> Result<String, Box<dyn Error>> {
+    let mut file = std::fs::File::open(path)?;
+    let len = file.metadata()?.len();
+    let mut out = String::new();
+    if len > max_bytes {
+        file.seek(SeekFrom::Start(len - max_bytes))?;
+        out.push_str("[...]\n");
+    }
+    let mut buf = Vec::new();
+    file.read_to_end(&mut buf)?;
+    out.push_str(&String::from_utf8_lossy(&buf));
+    Ok(out)
+}
+
+// Session directory names start with a timestamp, so sorting by name sorts by age
+fn list_session_dirs() -> Vec<PathBuf> {
+    let mut out = Vec::new();
+    if let Ok(entries) = std::fs::read_dir(PATH_PARTY.join("logs")) {
+        for entry in entries.flatten() {
+            if entry.file_type().map(|ft| ft.is_dir()).unwrap_or(false) {
+                out.push(entry.path());
+            }
+        }
+    }
+    out.sort();
+    out
+}
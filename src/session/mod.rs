@@ -0,0 +1,244 @@
+mod forward;
+mod layout;
+mod logs;
+mod network;
+mod proctree;
+mod readiness;
+mod supervisor;
+
+use crossbeam_channel::{Sender, unbounded};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use crate::handler::Handler;
+use crate::launch::LaunchPlan;
+use crate::util::{AudioStream, log_error, log_info};
+
+pub use logs::{last_session_dir, read_log_tail, session_log_files};
+
+#[derive(Clone, PartialEq)]
+pub enum InstanceState {
+    Waiting,
+    Running,
+    Exited,
+    Crashed,
+    Killed,
+    FailedToStart,
+}
+
+#[derive(Clone)]
+pub struct InstanceStatus {
+    pub player: usize,
+    pub profile: String,
+    pub state: InstanceState,
+    pub pid: Option<u32>,
+    pub started: Option<Instant>,
+    pub stopped: Option<Instant>,
+    pub exit_code: Option<i32>,
+    pub error: Option<String>,
+    pub restarts: u32,
+    // A crashed instance keeps the session open for a relaunch until its notice is dismissed
+    pub dismissed: bool,
+    // The instance's gamescope process and all of its descendants
+    pub processes: Vec<u32>,
+    // Usage of all of the instance's processes, 100% = one CPU
+    pub cpu_percent: f32,
+    pub rss: u64,
+    // Volume and mute applied to every audio stream of the instance
+    pub volume: f32,
+    pub muted: bool,
+    pub streams: Vec<AudioStream>,
+    // Whether the physical pad feeding the instance's virtual pad is connected
+    pub pad_connected: Option<bool>,
+}
+
+impl InstanceStatus {
+    pub fn uptime(&self) -> Duration {
+        match self.started {
+            Some(started) => self.stopped.unwrap_or_else(Instant::now) - started,
+            None => Duration::ZERO,
+        }
+    }
+
+    // Instances that stopped without being asked to can be started again with the same plan
+    pub fn can_relaunch(&self) -> bool {
+        matches!(
+            self.state,
+            InstanceState::Exited | InstanceState::Crashed | InstanceState::Killed
+        )
+    }
+}
+
+#[derive(Clone)]
+pub struct SessionStatus {
+    pub game: String,
+    pub log_dir: Option<PathBuf>,
+    pub instances: Vec<InstanceStatus>,
+    pub ending: bool,
+    pub finished: bool,
+    pub warnings: Vec<String>,
+    // Setup step being run before the first instance starts
+    pub setup_step: Option<String>,
+    // Instance whose audio plays at full volume while every other instance is ducked
+    pub audio_focus: Option<usize>,
+}
+
+pub enum SessionCommand {
+    End,
+    Kill(usize),
+    Relaunch(usize),
+    Dismiss(usize),
+    SetVolume(usize, f32),
+    SetMuted(usize, bool),
+    FocusAudio(Option<usize>),
+}
+
+// A running game session. The supervisor thread owns the instance processes,
+// the UI reads its status and sends commands through this handle.
+pub struct Session {
+    status: Arc<Mutex<SessionStatus>>,
+    commands: Sender<SessionCommand>,
+    thread: Option<JoinHandle<()>>,
+    finish_handled: bool,
+}
+
+impl Session {
+    // The plan's setup steps are run on the supervisor thread, as creating Wine prefixes can
+    // take minutes
+    pub fn start(plan: LaunchPlan, handler: Option<Handler>, keep_logs: usize) -> Self {
+        logs::prune_session_dirs(keep_logs.saturating_sub(1));
+        let log_dir = match logs::create_session_dir(&plan.uid) {
+            Ok(dir) => {
+                log_info(&format!("Session logs: {}", dir.display()));
+                match plan.to_json() {
+                    Ok(json) => {
+                        let _ = std::fs::write(dir.join("plan.json"), json);
+                    }
+                    Err(err) => log_error(&format!("Couldn't serialize launch plan: {err}")),
+                }
+                Some(dir)
+            }
+            Err(err) => {
+                log_error(&format!("Couldn't create session log directory: {err}"));
+                None
+            }
+        };
+
+        let status = Arc::new(Mutex::new(SessionStatus {
+            game: plan.game.clone(),
+            log_dir: log_dir.clone(),
+            instances: plan
+                .instances
+                .iter()
+                .map(|instance| InstanceStatus {
+                    player: instance.player,
+                    profile: instance.profile.clone(),
+                    state: InstanceState::Waiting,
+                    pid: None,
+                    started: None,
+                    stopped: None,
+                    exit_code: None,
+                    error: None,
+                    restarts: 0,
+                    dismissed: false,
+                    processes: Vec::new(),
+                    cpu_percent: 0.0,
+                    rss: 0,
+                    volume: 1.0,
+                    muted: false,
+                    streams: Vec::new(),
+                    pad_connected: None,
+                })
+                .collect(),
+            ending: false,
+            finished: false,
+            warnings: Vec::new(),
+            setup_step: None,
+            audio_focus: None,
+        }));
+        let (tx, rx) = unbounded();
+        let thread_status = status.clone();
+        let thread = std::thread::spawn(move || {
+            supervisor::run(plan, handler, log_dir, thread_status, rx);
+        });
+        Self {
+            status,
+            commands: tx,
+            thread: Some(thread),
+            finish_handled: false,
+        }
+    }
+
+    pub fn status(&self) -> SessionStatus {
+        self.status.lock().unwrap().clone()
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.status.lock().unwrap().finished
+    }
+
+    // Returns true exactly once after the session has finished, for one-time cleanup
+    pub fn take_finished(&mut self) -> bool {
+        if self.finish_handled || !self.is_finished() {
+            return false;
+        }
+        self.finish_handled = true;
+        true
+    }
+
+    pub fn end(&self) {
+        let _ = self.commands.send(SessionCommand::End);
+    }
+
+    pub fn kill_instance(&self, i: usize) {
+        let _ = self.commands.send(SessionCommand::Kill(i));
+    }
+
+    pub fn relaunch_instance(&self, i: usize) {
+        let _ = self.commands.send(SessionCommand::Relaunch(i));
+    }
+
+    pub fn dismiss_instance(&self, i: usize) {
+        let _ = self.commands.send(SessionCommand::Dismiss(i));
+    }
+
+    pub fn set_volume(&self, i: usize, volume: f32) {
+        let _ = self.commands.send(SessionCommand::SetVolume(i, volume));
+    }
+
+    pub fn set_muted(&self, i: usize, muted: bool) {
+        let _ = self.commands.send(SessionCommand::SetMuted(i, muted));
+    }
+
+    pub fn focus_audio(&self, i: Option<usize>) {
+        let _ = self.commands.send(SessionCommand::FocusAudio(i));
+    }
+
+    // Blocks until the supervisor thread has stopped every instance
+    pub fn wait(&mut self) {
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Drop for Session {
+    fn drop(&mut self) {
+        if !self.is_finished() {
+            self.end();
+        }
+        self.wait();
+    }
+}
+
+pub fn format_duration(d: Duration) -> String {
+    let secs = d.as_secs();
+    format!(
+        "{:02}:{:02}:{:02}",
+        secs / 3600,
+        (secs / 60) % 60,
+        secs % 60
+    )
+}
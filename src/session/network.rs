@@ -0,0 +1,199 @@
+use std::error::Error;
+use std::process::{Child, Command, Stdio};
+use std::time::{Duration, Instant};
+
+use crate::launch::LanPlan;
+use crate::util::log_info;
+
+// A private virtual LAN for a session, built without root:
+// a hub user+network namespace holds a bridge, and every instance gets a network namespace
+// inside the hub's user namespace, connected to the bridge by a veth pair. The bridge forwards
+// broadcasts, so LAN discovery between instances works like between separate PCs.
+// With an uplink, slirp4netns gives the hub internet access and the hub NATs the instances.
+pub struct VirtualLan {
+    hub: Child,
+    // One process per instance keeping its network namespace alive
+    holders: Vec<Child>,
+    uplink: Option<Child>,
+}
+
+impl VirtualLan {
+    pub fn start(lan: &LanPlan, addresses: &[String]) -> Result<VirtualLan, Box<dyn Error>> {
+        // Maps us to root in the hub so we may configure its network
+        let hub = Command::new("unshare")
+            .args(["--user", "--map-root-user", "--net", "sleep", "infinity"])
+            .stdin(Stdio::null())
+            .spawn()?;
+        let mut this = VirtualLan {
+            hub,
+            holders: Vec::new(),
+            uplink: None,
+        };
+        let hub_pid = this.hub.id().to_string();
+        wait_for_ns(this.hub.id())?;
+
+        let gateway = format!("{}.1", lan.subnet);
+        this.run_in(&hub_pid, &["ip", "link", "set", "lo", "up"])?;
+        this.run_in(&hub_pid, &["ip", "link", "add", "br0", "type", "bridge"])?;
+        this.run_in(
+            &hub_pid,
+            &["ip", "addr", "add", &format!("{gateway}/24"), "dev", "br0"],
+        )?;
+        this.run_in(&hub_pid, &["ip", "link", "set", "br0", "up"])?;
+
+        for (i, address) in addresses.iter().enumerate() {
+            let holder = Command::new("nsenter")
+                .args([
+                    "-t", &hub_pid, "-U", "-n", "--", "unshare", "--net", "sleep", "infinity",
+                ])
+                .stdin(Stdio::null())
+                .spawn()?;
+            let holder_pid = holder.id().to_string();
+            this.holders.push(holder);
+            wait_for_ns(this.holders[i].id())?;
+
+            let veth = format!("veth{i}");
+            this.run_in(
+                &hub_pid,
+                &[
+                    "ip",
+                    "link",
+                    "add",
+                    &veth,
+                    "type",
+                    "veth",
+                    "peer",
+                    "name",
+                    "eth0",
+                    "netns",
+                    &holder_pid,
+                ],
+            )?;
+            this.run_in(
+                &hub_pid,
+                &["ip", "link", "set", &veth, "master", "br0", "up"],
+            )?;
+            this.run_in(&holder_pid, &["ip", "link", "set", "lo", "up"])?;
+            this.run_in(
+                &holder_pid,
+                &["ip", "addr", "add", &format!("{address}/24"), "dev", "eth0"],
+            )?;
+            this.run_in(&holder_pid, &["ip", "link", "set", "eth0", "up"])?;
+            this.run_in(
+                &holder_pid,
+                &["ip", "route", "add", "default", "via", &gateway],
+            )?;
+        }
+
+        if lan.uplink {
+            let uplink = Command::new("slirp4netns")
+                .args([
+                    "--configure",
+                    "--mtu=65520",
+                    "--disable-host-loopback",
+                    &hub_pid,
+                    "tap0",
+                ])
+                .stdin(Stdio::null())
+                .stdout(Stdio::null())
+                .spawn()?;
+            this.uplink = Some(uplink);
+            // slirp4netns configures tap0 asynchronously
+            let deadline = Instant::now() + Duration::from_secs(5);
+            while this
+                .run_in(&hub_pid, &["ip", "link", "show", "tap0"])
+                .is_err()
+            {
+                if Instant::now() > deadline {
+                    return Err("slirp4netns didn't set up the uplink".into());
+                }
+                std::thread::sleep(Duration::from_millis(100));
+            }
+            this.run_in(&hub_pid, &["sysctl", "-qw", "net.ipv4.ip_forward=1"])?;
+            this.run_in(
+                &hub_pid,
+                &[
+                    "nft",
+                    &format!(
+                        "add table ip partydeck; add chain ip partydeck post {{ type nat hook postrouting priority 100; }}; add rule ip partydeck post ip saddr {}.0/24 oifname tap0 masquerade",
+                        lan.subnet
+                    ),
+                ],
+            )?;
+        }
+
+        log_info(&format!(
+            "Virtual LAN {}.0/24 ready for {} instances",
+            lan.subnet,
+            addresses.len()
+        ));
+        Ok(this)
+    }
+
+    // Commands that move instance `i` into its namespace. Entering the hub's user namespace
+    // makes us root there, so a nested user namespace maps us back to our own uid for the game.
+    pub fn enter_args(&self, i: usize) -> Vec<String> {
+        let Some(holder) = self.holders.get(i) else {
+            return Vec::new();
+        };
+        let (uid, gid) = unsafe { (libc::getuid(), libc::getgid()) };
+        vec![
+            "nsenter".to_string(),
+            "-t".to_string(),
+            holder.id().to_string(),
+            "-U".to_string(),
+            "-n".to_string(),
+            "--".to_string(),
+            "unshare".to_string(),
+            "--user".to_string(),
+            format!("--map-user={uid}"),
+            format!("--map-group={gid}"),
+            "--".to_string(),
+        ]
+    }
+
+    // Runs a command as root in the user and network namespace of `pid`
+    fn run_in(&self, pid: &str, args: &[&str]) -> Result<(), Box<dyn Error>> {
+        let out = Command::new("nsenter")
+            .args(["-t", pid, "-U", "-n", "--"])
+            .args(args)
+            .stdin(Stdio::null())
+            .output()?;
+        if !out.status.success() {
+            return Err(format!(
+                "\"{}\" failed: {}",
+                args.join(" "),
+                String::from_utf8_lossy(&out.stderr).trim()
+            )
+            .into());
+        }
+        Ok(())
+    }
+}
+
+impl Drop for VirtualLan {
+    fn drop(&mut self) {
+        // The namespaces go away with the last process in them
+        let children = self
+            .uplink
+            .iter_mut()
+            .chain(self.holders.iter_mut())
+            .chain(std::iter::once(&mut self.hub));
+        for child in children {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
+}
+
+// unshare creates the namespaces before exec'ing sleep, so wait for that exec
+fn wait_for_ns(pid: u32) -> Result<(), Box<dyn Error>> {
+    let deadline = Instant::now() + Duration::from_secs(2);
+    while std::fs::read_to_string(format!("/proc/{pid}/comm")).map(|c| c.trim() != "sleep")? {
+        if Instant::now() > deadline {
+            return Err("Timed out creating network namespace".into());
+        }
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    Ok(())
+}
@@ -0,0 +1,78 @@
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+use std::time::Instant;
+use x11rb::connection::Connection;
+use x11rb::protocol::xproto::{AtomEnum, ConnectionExt};
+
+use crate::launch::Readiness;
+use crate::session::proctree::{process_name, process_tree};
+
+// Checks whether an instance started at `started` with gamescope pid `pid` is ready.
+// `log_offset` is where the instance's log ended when it was started.
+pub fn is_ready(
+    ready: &Readiness,
+    pid: u32,
+    started: Instant,
+    log: Option<&Path>,
+    log_offset: u64,
+) -> bool {
+    match ready {
+        Readiness::Delay { seconds } => started.elapsed().as_secs_f32() >= *seconds,
+        Readiness::Window => has_window(pid),
+        Readiness::Process { name } => process_tree(pid)
+            .into_iter()
+            .any(|p| process_name(p).is_some_and(|n| &n == name)),
+        Readiness::File { path } => Path::new(path).exists(),
+        Readiness::LogLine { text } => match log {
+            Some(log) => log_contains(log, log_offset, text),
+            None => false,
+        },
+    }
+}
+
+// Looks for a top-level X11 window owned by the given process
+fn has_window(pid: u32) -> bool {
+    let Ok((conn, screen)) = x11rb::connect(None) else {
+        return false;
+    };
+    let root = conn.setup().roots[screen].root;
+    let atom = |name: &[u8]| {
+        conn.intern_atom(false, name)
+            .ok()
+            .and_then(|c| c.reply().ok())
+            .map(|r| r.atom)
+    };
+    let (Some(client_list), Some(wm_pid)) = (atom(b"_NET_CLIENT_LIST"), atom(b"_NET_WM_PID"))
+    else {
+        return false;
+    };
+    let windows: Vec<u32> = match conn
+        .get_property(false, root, client_list, AtomEnum::WINDOW, 0, u32::MAX)
+        .ok()
+        .and_then(|c| c.reply().ok())
+    {
+        Some(reply) => reply.value32().map(|v| v.collect()).unwrap_or_default(),
+        None => return false,
+    };
+    windows.into_iter().any(|window| {
+        conn.get_property(false, window, wm_pid, AtomEnum::CARDINAL, 0, 1)
+            .ok()
+            .and_then(|c| c.reply().ok())
+            .and_then(|r| r.value32().and_then(|mut v| v.next()))
+            == Some(pid)
+    })
+}
+
+fn log_contains(log: &Path, offset: u64, text: &str) -> bool {
+    let Ok(mut file) = std::fs::File::open(log) else {
+        return false;
+    };
+    if file.seek(SeekFrom::Start(offset)).is_err() {
+        return false;
+    }
+    let mut buf = Vec::new();
+    if file.read_to_end(&mut buf).is_err() {
+        return false;
+    }
+    String::from_utf8_lossy(&buf).contains(text)
+}
@@ -0,0 +1,605 @@
+use crossbeam_channel::{Receiver, RecvTimeoutError};
+use std::error::Error;
+use std::fs::OpenOptions;
+use std::os::unix::process::{CommandExt, ExitStatusExt};
+use std::path::PathBuf;
+use std::process::{Child, Command, Stdio};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::handler::Handler;
+use crate::launch::{Bind, InstancePlan, LaunchPlan, Readiness, SetupStep, add_bwrap_binds};
+use crate::session::forward::PadForwarder;
+use crate::session::layout::{load_session_script, session_script_name};
+use crate::session::network::VirtualLan;
+use crate::session::proctree::{clock_ticks, cpu_ticks, process_name, process_tree, rss_bytes};
+use crate::session::readiness::is_ready;
+use crate::session::{InstanceState, SessionCommand, SessionStatus};
+use crate::util::{
+    kwin_dbus_unload_script, log_error, log_info, remove_null_sink, scan_audio_streams,
+    set_stream_muted, set_stream_volume,
+};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+const TREE_INTERVAL: Duration = Duration::from_secs(1);
+const READY_INTERVAL: Duration = Duration::from_millis(500);
+// How long gamescope may keep running without its game before the instance counts as crashed
+const GAME_GONE_TIMEOUT: Duration = Duration::from_secs(3);
+// How long instances get to exit after SIGTERM before they're killed
+const TERM_TIMEOUT: Duration = Duration::from_secs(5);
+// Volume of instances without audio focus while another instance has it
+const DUCK_VOLUME: f32 = 0.25;
+
+// A started instance. gamescope is the process we spawn; the game runs under its bwrap child.
+struct Tracked {
+    child: Child,
+    started: Instant,
+    // Length of the instance's log before it was started
+    log_offset: u64,
+    seen_game: bool,
+    game_gone_since: Option<Instant>,
+    crashed: bool,
+    // CPU ticks used by the process tree at the last tree check
+    last_cpu: Option<(u64, Instant)>,
+}
+
+struct Supervisor {
+    plan: LaunchPlan,
+    log_dir: Option<PathBuf>,
+    status: Arc<Mutex<SessionStatus>>,
+    slots: Vec<Option<Tracked>>,
+    script_loaded: bool,
+    last_tree_check: Instant,
+    // Audio streams that already got their instance's volume and mute
+    configured_streams: Vec<u32>,
+    lan: Option<VirtualLan>,
+    // Virtual pad of each instance, when the plan uses them
+    forwarders: Vec<Option<PadForwarder>>,
+    // Set once the session is being ended, so the instances' exits aren't taken for crashes
+    terminating: bool,
+}
+
+pub fn run(
+    plan: LaunchPlan,
+    handler: Option<Handler>,
+    log_dir: Option<PathBuf>,
+    status: Arc<Mutex<SessionStatus>>,
+    commands: Receiver<SessionCommand>,
+) {
+    log_info(&format!("Session started for {}", plan.game));
+
+    let mut sup = Supervisor {
+        slots: plan.instances.iter().map(|_| None).collect(),
+        plan,
+        log_dir,
+        status,
+        script_loaded: false,
+        last_tree_check: Instant::now(),
+        configured_streams: Vec::new(),
+        lan: None,
+        forwarders: Vec::new(),
+        terminating: false,
+    };
+    let mut ending = false;
+
+    if let Err(err) = sup.run_setup(handler.as_ref()) {
+        sup.fail_to_start(&format!("Couldn't prepare the session: {err}"));
+        ending = true;
+    }
+
+    if let Some(lan) = &sup.plan.network
+        && !ending
+    {
+        let addresses: Vec<String> = sup
+            .plan
+            .instances
+            .iter()
+            .filter_map(|instance| instance.address.clone())
+            .collect();
+        match VirtualLan::start(lan, &addresses) {
+            Ok(lan) => sup.lan = Some(lan),
+            Err(err) => {
+                sup.fail_to_start(&format!("Couldn't set up the virtual LAN: {err}"));
+                ending = true;
+            }
+        }
+    }
+
+    // Every virtual pad exists before the first instance starts, so each can be hidden from
+    // the other instances
+    if sup.plan.virtual_pads && !ending {
+        for (i, instance) in sup.plan.instances.iter().enumerate() {
+            let forwarder = match instance.pads.is_empty() {
+                false => match PadForwarder::start(
+                    i,
+                    &instance.pads,
+                    instance.mapping.clone().unwrap_or_default(),
+                ) {
+                    Ok(forwarder) => Some(forwarder),
+                    Err(err) => {
+                        let error =
+                            format!("Couldn't create player {}'s virtual pad: {err}", i + 1);
+                        sup.forwarders.clear();
+                        sup.fail_to_start(&error);
+                        ending = true;
+                        break;
+                    }
+                },
+                true => None,
+            };
+            sup.forwarders.push(forwarder);
+        }
+    }
+
+    for i in 0..sup.plan.instances.len() {
+        if ending {
+            break;
+        }
+        sup.spawn(i);
+
+        if i < sup.plan.instances.len() - 1 {
+            ending = sup.wait_ready(i, &commands);
+        }
+    }
+
+    // Instances that never got started because the session was ended while waiting for readiness
+    {
+        let mut status = sup.status.lock().unwrap();
+        for instance in status.instances.iter_mut() {
+            if instance.state == InstanceState::Waiting {
+                instance.state = InstanceState::Killed;
+            }
+        }
+    }
+
+    while !ending && sup.keeps_running() {
+        ending = sup.handle_commands(&commands, POLL_INTERVAL);
+        sup.poll();
+    }
+
+    if ending {
+        sup.status.lock().unwrap().ending = true;
+        sup.terminate_all();
+    }
+
+    if sup.script_loaded
+        && let Err(err) = kwin_dbus_unload_script(&session_script_name())
+    {
+        log_error(&format!("Couldn't unload KWin script: {err}"));
+    }
+    sup.forwarders.clear();
+    for step in &sup.plan.setup {
+        if let SetupStep::CreateNullSink { name } = step {
+            remove_null_sink(name);
+        }
+    }
+    log_info(&format!("Session finished for {}", sup.plan.game));
+    sup.status.lock().unwrap().finished = true;
+}
+
+impl Supervisor {
+    // The session stays open while an instance runs or a crashed one may still be relaunched
+    fn keeps_running(&self) -> bool {
+        self.slots.iter().any(|c| c.is_some())
+            || self
+                .status
+                .lock()
+                .unwrap()
+                .instances
+                .iter()
+                .any(|instance| instance.state == InstanceState::Crashed && !instance.dismissed)
+    }
+
+    fn run_setup(&self, h: Option<&Handler>) -> Result<(), Box<dyn Error>> {
+        for step in &self.plan.setup {
+            let description = step.describe();
+            log_info(&format!("Setup: {description}"));
+            self.status.lock().unwrap().setup_step = Some(description);
+            let result = step.run(h);
+            self.status.lock().unwrap().setup_step = None;
+            result?;
+        }
+        Ok(())
+    }
+
+    // Marks every instance as failed when the session can't be set up
+    fn fail_to_start(&self, error: &str) {
+        log_error(error);
+        let mut status = self.status.lock().unwrap();
+        status.warnings.push(error.to_string());
+        for instance in status.instances.iter_mut() {
+            instance.state = InstanceState::FailedToStart;
+            instance.error = Some(error.to_string());
+        }
+    }
+
+    fn spawn(&mut self, i: usize) {
+        let instance: &InstancePlan = &self.plan.instances[i];
+        let mut argv = match &self.lan {
+            Some(lan) => lan.enter_args(i),
+            None => Vec::new(),
+        };
+        let mut instance_argv = instance.argv.clone();
+        // Hide the other players' virtual pads
+        let masks: Vec<Bind> = self
+            .forwarders
+            .iter()
+            .enumerate()
+            .filter(|(n, _)| *n != i)
+            .filter_map(|(_, forwarder)| forwarder.as_ref())
+            .flat_map(|forwarder| forwarder.nodes())
+            .map(|node| Bind {
+                src: "/dev/null".to_string(),
+                dest: node.clone(),
+            })
+            .collect();
+        add_bwrap_binds(&mut instance_argv, &masks);
+        argv.extend(instance_argv);
+        let mut cmd = Command::new(&argv[0]);
+        cmd.args(&argv[1..])
+            .envs(&self.plan.env)
+            .envs(&instance.env)
+            .current_dir(&self.plan.gamedir)
+            // Own process group so the whole instance can be signalled at once
+            .process_group(0);
+
+        // gamescope and the game both write to the instance's log, which is appended to on relaunch
+        if let Some(dir) = &self.log_dir {
+            let log = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(dir.join(log_name(i)));
+            match log.and_then(|f| Ok((f.try_clone()?, f))) {
+                Ok((stdout, stderr)) => {
+                    cmd.stdout(Stdio::from(stdout)).stderr(Stdio::from(stderr));
+                }
+                Err(err) => log_error(&format!("Couldn't open log for instance {}: {err}", i + 1)),
+            }
+            if self.plan.win {
+                let proton_logs = dir.join(format!("proton-player{}", i + 1));
+                let _ = std::fs::create_dir_all(&proton_logs);
+                cmd.env("PROTON_LOG", "1")
+                    .env("PROTON_LOG_DIR", proton_logs);
+            }
+        }
+
+        let log_offset = self
+            .log_path(i)
+            .and_then(|log| std::fs::metadata(log).ok())
+            .map(|m| m.len())
+            .unwrap_or(0);
+        let result = cmd.spawn();
+        {
+            let mut status = self.status.lock().unwrap();
+            let entry = &mut status.instances[i];
+            match result {
+                Ok(child) => {
+                    log_info(&format!("Started instance {} (pid {})", i + 1, child.id()));
+                    entry.state = InstanceState::Running;
+                    entry.pid = Some(child.id());
+                    entry.started = Some(Instant::now());
+                    entry.stopped = None;
+                    entry.exit_code = None;
+                    entry.error = None;
+                    entry.dismissed = false;
+                    self.slots[i] = Some(Tracked {
+                        child,
+                        started: Instant::now(),
+                        log_offset,
+                        seen_game: false,
+                        game_gone_since: None,
+                        crashed: false,
+                        last_cpu: None,
+                    });
+                }
+                Err(err) => {
+                    log_error(&format!("Couldn't start instance {}: {err}", i + 1));
+                    entry.state = InstanceState::FailedToStart;
+                    entry.error = Some(err.to_string());
+                    return;
+                }
+            }
+        }
+        self.update_kwin_script();
+    }
+
+    fn log_path(&self, i: usize) -> Option<PathBuf> {
+        self.log_dir.as_ref().map(|dir| dir.join(log_name(i)))
+    }
+
+    // Keeps the session responsive while waiting for instance `i` to become ready.
+    // Returns true if the session should end.
+    fn wait_ready(&mut self, i: usize, commands: &Receiver<SessionCommand>) -> bool {
+        let ready = self.plan.instances[i].ready.clone();
+        let timeout = Duration::from_secs_f32(self.plan.ready_timeout);
+        let log = self.log_path(i);
+        let mut last_check = Instant::now();
+        loop {
+            let Some(tracked) = &self.slots[i] else {
+                // Exited or failed to start, nothing to wait for
+                return false;
+            };
+            if last_check.elapsed() >= READY_INTERVAL || matches!(ready, Readiness::Delay { .. }) {
+                last_check = Instant::now();
+                if is_ready(
+                    &ready,
+                    tracked.child.id(),
+                    tracked.started,
+                    log.as_deref(),
+                    tracked.log_offset,
+                ) {
+                    log_info(&format!(
+                        "Instance {} ready after {:.1}s",
+                        i + 1,
+                        tracked.started.elapsed().as_secs_f32()
+                    ));
+                    return false;
+                }
+            }
+            if tracked.started.elapsed() >= timeout {
+                let warning = format!(
+                    "Player {}'s instance didn't become ready within {}s (waited for {}), starting the next one anyway",
+                    i + 1,
+                    self.plan.ready_timeout,
+                    ready.describe()
+                );
+                log_error(&warning);
+                self.status.lock().unwrap().warnings.push(warning);
+                return false;
+            }
+            let remaining = match &ready {
+                Readiness::Delay { seconds } => {
+                    Duration::from_secs_f32(*seconds).saturating_sub(tracked.started.elapsed())
+                }
+                _ => POLL_INTERVAL,
+            };
+            if self.handle_commands(commands, remaining.min(POLL_INTERVAL)) {
+                return true;
+            }
+            self.poll();
+        }
+    }
+
+    fn update_kwin_script(&mut self) {
+        let pids: Vec<Option<u32>> = self
+            .slots
+            .iter()
+            .map(|slot| slot.as_ref().map(|t| t.child.id()))
+            .collect();
+        match load_session_script(
+            &self.plan.kwin_script,
+            self.plan.instances.len(),
+            &pids,
+            self.script_loaded,
+        ) {
+            Ok(()) => self.script_loaded = true,
+            Err(err) => log_error(&format!("Couldn't load KWin script: {err}")),
+        }
+    }
+
+    // Waits up to `timeout` for a command. Returns true if the session should end.
+    fn handle_commands(&mut self, commands: &Receiver<SessionCommand>, timeout: Duration) -> bool {
+        match commands.recv_timeout(timeout) {
+            Ok(SessionCommand::End) => true,
+            Ok(SessionCommand::Kill(i)) => {
+                if let Some(Some(tracked)) = self.slots.get_mut(i) {
+                    log_info(&format!("Killing instance {}", i + 1));
+                    signal_group(&tracked.child, libc::SIGKILL);
+                    let _ = tracked.child.wait();
+                    self.mark_stopped(i, InstanceState::Killed, None);
+                    self.slots[i] = None;
+                }
+                false
+            }
+            Ok(SessionCommand::Relaunch(i)) => {
+                if i < self.slots.len() && self.slots[i].is_none() {
+                    log_info(&format!("Relaunching instance {}", i + 1));
+                    self.status.lock().unwrap().instances[i].restarts += 1;
+                    self.spawn(i);
+                }
+                false
+            }
+            Ok(SessionCommand::Dismiss(i)) => {
+                if let Some(instance) = self.status.lock().unwrap().instances.get_mut(i) {
+                    instance.dismissed = true;
+                }
+                false
+            }
+            Ok(SessionCommand::SetVolume(i, volume)) => {
+                if let Some(instance) = self.status.lock().unwrap().instances.get_mut(i) {
+                    instance.volume = volume;
+                }
+                self.update_audio(true);
+                false
+            }
+            Ok(SessionCommand::SetMuted(i, muted)) => {
+                if let Some(instance) = self.status.lock().unwrap().instances.get_mut(i) {
+                    instance.muted = muted;
+                }
+                self.update_audio(true);
+                false
+            }
+            Ok(SessionCommand::FocusAudio(focus)) => {
+                self.status.lock().unwrap().audio_focus = focus;
+                self.update_audio(true);
+                false
+            }
+            Err(RecvTimeoutError::Timeout) => false,
+            // The UI dropped its handle without ending the session
+            Err(RecvTimeoutError::Disconnected) => true,
+        }
+    }
+
+    fn poll(&mut self) {
+        if self.last_tree_check.elapsed() >= TREE_INTERVAL {
+            self.last_tree_check = Instant::now();
+            self.check_trees();
+        }
+        self.reap();
+        self.update_pad_status();
+    }
+
+    fn update_pad_status(&self) {
+        if self.forwarders.is_empty() {
+            return;
+        }
+        let mut status = self.status.lock().unwrap();
+        for (i, forwarder) in self.forwarders.iter().enumerate() {
+            status.instances[i].pad_connected = forwarder.as_ref().map(|f| f.connected());
+        }
+    }
+
+    // Collects instances that exited on their own
+    fn reap(&mut self) {
+        for i in 0..self.slots.len() {
+            let Some(tracked) = &mut self.slots[i] else {
+                continue;
+            };
+            if let Ok(Some(exit)) = tracked.child.try_wait() {
+                let code = exit.code().or_else(|| exit.signal().map(|s| 128 + s));
+                let state = match (tracked.crashed, self.terminating, code) {
+                    (false, _, Some(0)) => InstanceState::Exited,
+                    (false, true, _) => InstanceState::Killed,
+                    _ => InstanceState::Crashed,
+                };
+                match state {
+                    InstanceState::Crashed => {
+                        log_error(&format!("Instance {} crashed ({exit})", i + 1))
+                    }
+                    InstanceState::Killed => {
+                        log_info(&format!("Instance {} stopped ({exit})", i + 1))
+                    }
+                    _ => log_info(&format!("Instance {} exited with {exit}", i + 1)),
+                }
+                self.mark_stopped(i, state, code);
+                self.slots[i] = None;
+            }
+        }
+    }
+
+    // gamescope can outlive the game it hosts, leaving an empty tile.
+    // Treat an instance whose game process tree disappeared as crashed.
+    fn check_trees(&mut self) {
+        for i in 0..self.slots.len() {
+            let Some(tracked) = &mut self.slots[i] else {
+                continue;
+            };
+            let tree = process_tree(tracked.child.id());
+            let has_game = tree
+                .iter()
+                .skip(1)
+                .any(|pid| process_name(*pid).is_some_and(|name| name == "bwrap"));
+            if has_game {
+                tracked.seen_game = true;
+                tracked.game_gone_since = None;
+            } else if tracked.seen_game && !tracked.crashed {
+                let since = *tracked.game_gone_since.get_or_insert_with(Instant::now);
+                if since.elapsed() >= GAME_GONE_TIMEOUT {
+                    log_error(&format!(
+                        "Game process of instance {} is gone, stopping gamescope",
+                        i + 1
+                    ));
+                    tracked.crashed = true;
+                    signal_group(&tracked.child, libc::SIGTERM);
+                }
+            }
+
+            // Processes that exited since the last check take their ticks with them
+            let ticks: u64 = tree.iter().filter_map(|pid| cpu_ticks(*pid)).sum();
+            let rss: u64 = tree.iter().filter_map(|pid| rss_bytes(*pid)).sum();
+            let now = Instant::now();
+            let cpu_percent = match tracked.last_cpu {
+                Some((last, at)) => {
+                    let secs = (now - at).as_secs_f32();
+                    ticks.saturating_sub(last) as f32 / clock_ticks() / secs * 100.0
+                }
+                None => 0.0,
+            };
+            tracked.last_cpu = Some((ticks, now));
+
+            let mut status = self.status.lock().unwrap();
+            let entry = &mut status.instances[i];
+            entry.processes = tree;
+            entry.cpu_percent = cpu_percent;
+            entry.rss = rss;
+        }
+        self.update_audio(false);
+    }
+
+    // Maps audio streams to instances through their process trees and applies each instance's
+    // volume and mute. Unless `all` is set, only new streams are touched, so changes made in
+    // other mixers stick.
+    fn update_audio(&mut self, all: bool) {
+        let streams = scan_audio_streams();
+        let mut status = self.status.lock().unwrap();
+        let focus = status.audio_focus;
+        for (i, instance) in status.instances.iter_mut().enumerate() {
+            let target = match focus {
+                Some(f) if f != i => instance.volume * DUCK_VOLUME,
+                _ => instance.volume,
+            };
+            instance.streams = streams
+                .iter()
+                .filter(|s| s.pid.is_some_and(|pid| instance.processes.contains(&pid)))
+                .cloned()
+                .collect();
+            for stream in &mut instance.streams {
+                if !all && self.configured_streams.contains(&stream.index) {
+                    continue;
+                }
+                if (stream.volume - target).abs() > 0.005 {
+                    set_stream_volume(stream.index, target);
+                    stream.volume = target;
+                }
+                if stream.muted != instance.muted {
+                    set_stream_muted(stream.index, instance.muted);
+                    stream.muted = instance.muted;
+                }
+                if !self.configured_streams.contains(&stream.index) {
+                    self.configured_streams.push(stream.index);
+                }
+            }
+        }
+    }
+
+    fn terminate_all(&mut self) {
+        self.terminating = true;
+        for tracked in self.slots.iter().flatten() {
+            signal_group(&tracked.child, libc::SIGTERM);
+        }
+        let deadline = Instant::now() + TERM_TIMEOUT;
+        while Instant::now() < deadline && self.slots.iter().any(|c| c.is_some()) {
+            std::thread::sleep(POLL_INTERVAL);
+            self.reap();
+        }
+        for i in 0..self.slots.len() {
+            if let Some(mut tracked) = self.slots[i].take() {
+                signal_group(&tracked.child, libc::SIGKILL);
+                let _ = tracked.child.wait();
+                self.mark_stopped(i, InstanceState::Killed, None);
+            }
+        }
+    }
+
+    fn mark_stopped(&self, i: usize, state: InstanceState, code: Option<i32>) {
+        let mut status = self.status.lock().unwrap();
+        let entry = &mut status.instances[i];
+        entry.state = state;
+        entry.exit_code = code;
+        entry.stopped = Some(Instant::now());
+        entry.processes.clear();
+        entry.cpu_percent = 0.0;
+        entry.rss = 0;
+    }
+}
+
+fn log_name(i: usize) -> String {
+    format!("player{}.log", i + 1)
+}
+
+fn signal_group(child: &Child, signal: i32) {
+    // The instance was started as a process group leader, so its pid is the group id
+    unsafe {
+        libc::kill(-(child.id() as i32), signal);
+    }
+}
@@ -0,0 +1,162 @@
+use std::error::Error;
+use std::process::Command;
+
+// Name of the sink that muted players are routed to
+pub const MUTED_SINK: &str = "partydeck_muted";
+
+#[derive(Clone, PartialEq)]
+pub enum AudioOutput {
+    Default,
+    Sink(String),
+    Muted,
+}
+
+#[derive(Clone)]
+pub struct AudioSink {
+    pub name: String,
+    pub description: String,
+}
+
+// Lists output sinks through pactl, which works with both PulseAudio and pipewire-pulse
+pub fn scan_audio_sinks() -> Vec<AudioSink> {
+    let Ok(out) = Command::new("pactl")
+        .args(["list", "sinks"])
+        .env("LC_ALL", "C")
+        .output()
+    else {
+        return Vec::new();
+    };
+    let mut sinks: Vec<AudioSink> = Vec::new();
+    for line in String::from_utf8_lossy(&out.stdout).lines() {
+        let line = line.trim();
+        if let Some(name) = line.strip_prefix("Name: ") {
+            sinks.push(AudioSink {
+                name: name.to_string(),
+                description: name.to_string(),
+            });
+        } else if let Some(description) = line.strip_prefix("Description: ")
+            && let Some(sink) = sinks.last_mut()
+        {
+            sink.description = description.to_string();
+        }
+    }
+    sinks.retain(|sink| sink.name != MUTED_SINK);
+    sinks
+}
+
+// Loads a sink that discards everything played to it, unless it already exists
+pub fn create_null_sink(name: &str) -> Result<(), Box<dyn Error>> {
+    if null_sink_module(name).is_some() {
+        return Ok(());
+    }
+    let status = Command::new("pactl")
+        .arg("load-module")
+        .arg("module-null-sink")
+        .arg(format!("sink_name={name}"))
+        .arg(format!("sink_properties=device.description={name}"))
+        .status()?;
+    if !status.success() {
+        return Err(format!("Couldn't create audio sink {name}").into());
+    }
+    Ok(())
+}
+
+pub fn remove_null_sink(name: &str) {
+    if let Some(module) = null_sink_module(name) {
+        let _ = Command::new("pactl")
+            .args(["unload-module", &module])
+            .status();
+    }
+}
+
+// Index of the module-null-sink instance that created sink `name`
+fn null_sink_module(name: &str) -> Option<String> {
+    let out = Command::new("pactl")
+        .args(["list", "short", "modules"])
+        .output()
+        .ok()?;
+    let arg = format!("sink_name={name}");
+    String::from_utf8_lossy(&out.stdout)
+        .lines()
+        .find_map(|line| {
+            let mut fields = line.split('\t');
+            let index = fields.next()?;
+            let module = fields.next()?;
+            let args = fields.next().unwrap_or_default();
+            (module == "module-null-sink" && args.split_whitespace().any(|a| a == arg))
+                .then(|| index.to_string())
+        })
+}
+
+// A stream an application is playing, called a sink input by PulseAudio
+#[derive(Clone)]
+pub struct AudioStream {
+    pub index: u32,
+    pub pid: Option<u32>,
+    pub name: String,
+    // 1.0 = 100%
+    pub volume: f32,
+    pub muted: bool,
+}
+
+pub fn scan_audio_streams() -> Vec<AudioStream> {
+    let Ok(out) = Command::new("pactl")
+        .args(["list", "sink-inputs"])
+        .env("LC_ALL", "C")
+        .output()
+    else {
+        return Vec::new();
+    };
+    let mut streams: Vec<AudioStream> = Vec::new();
+    for line in String::from_utf8_lossy(&out.stdout).lines() {
+        let line = line.trim();
+        if let Some(index) = line.strip_prefix("Sink Input #") {
+            if let Ok(index) = index.parse() {
+                streams.push(AudioStream {
+                    index,
+                    pid: None,
+                    name: String::new(),
+                    volume: 1.0,
+                    muted: false,
+                });
+            }
+            continue;
+        }
+        let Some(stream) = streams.last_mut() else {
+            continue;
+        };
+        if let Some(mute) = line.strip_prefix("Mute: ") {
+            stream.muted = mute == "yes";
+        } else if let Some(volume) = line.strip_prefix("Volume: ") {
+            // "front-left: 65536 / 100% / 0.00 dB, ...", the first channel stands for all
+            if let Some(percent) = volume
+                .split('/')
+                .nth(1)
+                .and_then(|p| p.trim().trim_end_matches('%').parse::<f32>().ok())
+            {
+                stream.volume = percent / 100.0;
+            }
+        } else if let Some(pid) = line.strip_prefix("application.process.id = ") {
+            stream.pid = pid.trim_matches('"').parse().ok();
+        } else if let Some(name) = line.strip_prefix("application.name = ") {
+            stream.name = name.trim_matches('"').to_string();
+        }
+    }
+    streams
+}
+
+pub fn set_stream_volume(index: u32, volume: f32) {
+    let _ = Command::new("pactl")
+        .arg("set-sink-input-volume")
+        .arg(index.to_string())
+        .arg(format!("{}%", (volume * 100.0).round() as u32))
+        .status();
+}
+
+pub fn set_stream_muted(index: u32, muted: bool) {
+    let _ = Command::new("pactl")
+        .arg("set-sink-input-mute")
+        .arg(index.to_string())
+        .arg(if muted { "1" } else { "0" })
+        .status();
+}
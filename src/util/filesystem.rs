@@ -192,3 +192,15 @@ impl SanitizePath for String {
         sanitized
     }
 }
+
+// Resolves a command the way the shell would: paths are used as-is, bare names are looked up in PATH
+pub fn find_in_path(cmd: &str) -> Option<PathBuf> {
+    if cmd.contains('/') {
+        let path = PathBuf::from(cmd);
+        return path.is_file().then_some(path);
+    }
+    let path_var = std::env::var_os("PATH")?;
+    std::env::split_paths(&path_var)
+        .map(|dir| dir.join(cmd))
+        .find(|path| path.is_file())
+}
@@ -1,15 +1,46 @@
 // Re-export all utility functions from submodules
+mod audio;
 mod filesystem;
+mod prefixes;
 mod profiles;
+mod proton;
+mod runtimes;
+mod shortcuts;
 mod sys;
 mod updates;
+mod vdf;
 mod logger;
 
+// Re-export functions from audio
+pub use audio::{
+    AudioOutput, AudioSink, AudioStream, MUTED_SINK, create_null_sink, remove_null_sink,
+    scan_audio_sinks, scan_audio_streams, set_stream_muted, set_stream_volume,
+};
+
 // Re-export functions from profiles
-pub use profiles::{create_gamesave, create_profile, remove_guest_profiles, scan_profiles};
+pub use profiles::{
+    create_gamesave, create_profile, remove_guest_profile, remove_guest_profiles, scan_profiles,
+};
+
+// Re-export functions from prefixes
+pub use prefixes::{
+    PrefixInfo, PrefixStrategy, clone_prefix, delete_prefix, init_prefix, prefix_initialised,
+    prefix_path, prefix_template, reset_prefix, scan_prefixes,
+};
+
+// Re-export functions from proton
+pub use proton::{ProtonBuild, find_proton_build, scan_proton_builds, version_at_least};
+
+// Re-export functions from runtimes
+pub use runtimes::{find_runtime, scan_runtimes};
+
+// Re-export functions from shortcuts
+pub use shortcuts::{SteamShortcut, add_steam_shortcut, remove_steam_shortcut};
 
 // Re-export functions from filesystem
-pub use filesystem::{SanitizePath, copy_dir_recursive, get_rootpath, get_rootpath_handler};
+pub use filesystem::{
+    SanitizePath, copy_dir_recursive, find_in_path, get_rootpath, get_rootpath_handler,
+};
 
 // Re-export functions from launcher
 pub use sys::{
@@ -0,0 +1,186 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::path::{Path, PathBuf};
+
+use crate::paths::*;
+
+// How Wine prefixes are assigned to Proton instances
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub enum PrefixStrategy {
+    // Every instance uses PATH_PARTY/pfx
+    Shared,
+    // One prefix per player slot and runner, reused across games and profiles
+    PerSlot,
+    // One prefix per profile and runner, so a player's Wine settings follow them between slots
+    PerProfile,
+}
+
+impl PrefixStrategy {
+    pub fn label(&self) -> &'static str {
+        match self {
+            PrefixStrategy::Shared => "Shared",
+            PrefixStrategy::PerSlot => "Per player slot",
+            PrefixStrategy::PerProfile => "Per profile",
+        }
+    }
+}
+
+// Written into cloned prefixes, holding the path of their template
+const TEMPLATE_MARKER: &str = ".partydeck-template";
+
+pub struct PrefixInfo {
+    pub name: String,
+    pub path: PathBuf,
+    pub size: u64,
+}
+
+// Template prefix for runner key `key`, see Runner::template_key
+pub fn prefix_template(key: &str) -> PathBuf {
+    PATH_PARTY.join(format!("prefixes/template-{key}"))
+}
+
+// Resolves the prefix for player slot `slot` using `profile`.
+// Guest profiles get a new name every session, so they fall back to their slot's prefix.
+// Prefixes are kept apart per runner key (see Runner::template_key), since a prefix made by
+// one Wine version can break under another. A custom command has no key.
+pub fn prefix_path(
+    strategy: PrefixStrategy,
+    slot: usize,
+    profile: &str,
+    key: Option<&str>,
+) -> PathBuf {
+    let suffix = key.map(|key| format!("@{key}")).unwrap_or_default();
+    match strategy {
+        PrefixStrategy::Shared => PATH_PARTY.join("pfx"),
+        PrefixStrategy::PerProfile if !profile.is_empty() && !profile.starts_with('.') => {
+            PATH_PARTY.join(format!("prefixes/profile-{profile}{suffix}"))
+        }
+        _ => PATH_PARTY.join(format!("prefixes/slot{}{suffix}", slot + 1)),
+    }
+}
+
+// A prefix counts as initialised once Wine has written its registry
+pub fn prefix_initialised(path: &Path) -> bool {
+    path.join("system.reg").exists()
+}
+
+// Initialises a prefix by running `argv` (the runner's "wineboot -u") so it can be used as
+// the template for other prefixes
+pub fn init_prefix(
+    path: &Path,
+    argv: &[String],
+    env: &BTreeMap<String, String>,
+) -> Result<(), Box<dyn Error>> {
+    println!("Initialising Wine prefix {}", path.display());
+    std::fs::create_dir_all(path)?;
+    let Some((program, args)) = argv.split_first() else {
+        return Err("No command to initialise the Wine prefix with".into());
+    };
+    let status = std::process::Command::new(program)
+        .args(args)
+        .envs(env)
+        .env("WINEPREFIX", path)
+        .status()?;
+    if !status.success() || !prefix_initialised(path) {
+        return Err(format!("Couldn't initialise Wine prefix {}", path.display()).into());
+    }
+    Ok(())
+}
+
+// Copies a prefix, sharing file data with the source where the filesystem supports reflinks.
+// The copy remembers its template so it can be reset from it.
+pub fn clone_prefix(from: &Path, to: &Path) -> Result<(), Box<dyn Error>> {
+    println!("Cloning Wine prefix {} to {}", from.display(), to.display());
+    if let Some(parent) = to.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let status = std::process::Command::new("cp")
+        .arg("-a")
+        .arg("--reflink=auto")
+        .arg(from)
+        .arg(to)
+        .status()?;
+    if !status.success() {
+        return Err(format!("Couldn't copy {} to {}", from.display(), to.display()).into());
+    }
+    std::fs::write(to.join(TEMPLATE_MARKER), from.to_string_lossy().as_bytes())?;
+    Ok(())
+}
+
+// Deletes a prefix and recreates it from the template it was cloned from, or empty if it
+// wasn't cloned or its template is gone
+pub fn reset_prefix(path: &Path) -> Result<(), Box<dyn Error>> {
+    let template = std::fs::read_to_string(path.join(TEMPLATE_MARKER))
+        .ok()
+        .map(PathBuf::from);
+    delete_prefix(path)?;
+    match template {
+        Some(template) if prefix_initialised(&template) => clone_prefix(&template, path),
+        _ => {
+            std::fs::create_dir_all(path)?;
+            Ok(())
+        }
+    }
+}
+
+pub fn delete_prefix(path: &Path) -> Result<(), Box<dyn Error>> {
+    if path.exists() {
+        std::fs::remove_dir_all(path)?;
+    }
+    Ok(())
+}
+
+// Lists the shared prefix, the template and every per-slot/per-profile prefix with their sizes
+pub fn scan_prefixes() -> Vec<PrefixInfo> {
+    let mut out = Vec::new();
+    let shared = PATH_PARTY.join("pfx");
+    if shared.exists() {
+        out.push(PrefixInfo {
+            name: "Shared".to_string(),
+            size: dir_size(&shared),
+            path: shared,
+        });
+    }
+    if let Ok(entries) = std::fs::read_dir(PATH_PARTY.join("prefixes")) {
+        let mut paths: Vec<PathBuf> = entries
+            .flatten()
+            .filter(|e| e.file_type().map(|ft| ft.is_dir()).unwrap_or(false))
+            .map(|e| e.path())
+            .collect();
+        paths.sort();
+        for path in paths {
+            let name = path
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_default();
+            let (base, key) = match name.rsplit_once('@') {
+                Some((base, key)) => (base, format!(" ({key})")),
+                None => (name.as_str(), String::new()),
+            };
+            let name = match base {
+                n if n.starts_with("template-") => format!("Template ({})", &n[9..]),
+                n if n.starts_with("slot") => format!("Player {}{key}", &n[4..]),
+                n if n.starts_with("profile-") => format!("Profile {}{key}", &n[8..]),
+                _ => name.clone(),
+            };
+            out.push(PrefixInfo {
+                name,
+                size: dir_size(&path),
+                path,
+            });
+        }
+    }
+    out
+}
+
+fn dir_size(path: &Path) -> u64 {
+    walkdir::WalkDir::new(path)
+        .follow_links(false)
+        .into_iter()
+        .flatten()
+        .filter(|e| e.file_type().is_file())
+        .filter_map(|e| e.metadata().ok())
+        .map(|m| m.len())
+        .sum()
+}
@@ -107,6 +107,14 @@ pub fn scan_profiles(include_guest: bool) -> Vec<String> {
     out
 }
 
+// Removes one session's guest profile, leaving named profiles untouched
+pub fn remove_guest_profile(name: &str) -> Result<(), Box<dyn Error>> {
+    if name.starts_with('.') {
+        std::fs::remove_dir_all(PATH_PARTY.join("profiles").join(name))?;
+    }
+    Ok(())
+}
+
 pub fn remove_guest_profiles() -> Result<(), Box<dyn Error>> {
     let path_profiles = PATH_PARTY.join("profiles");
     let entries = std::fs::read_dir(&path_profiles)?;
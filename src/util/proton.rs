@@ -0,0 +1,114 @@
+use std::path::{Path, PathBuf};
+
+use crate::paths::*;
+
+#[derive(Clone)]
+pub struct ProtonBuild {
+    pub name: String,
+    pub version: String,
+    pub path: PathBuf,
+}
+
+impl ProtonBuild {
+    pub fn display(&self) -> String {
+        if self.version.is_empty() || self.version == self.name {
+            self.name.clone()
+        } else {
+            format!("{} ({})", self.name, self.version)
+        }
+    }
+}
+
+// Finds Proton builds in Steam's compatibilitytools.d and steamapps/common,
+// plus any extra directories, which may either be a build or contain builds
+pub fn scan_proton_builds(extra_dirs: &[String]) -> Vec<ProtonBuild> {
+    let mut out = Vec::new();
+
+    scan_dir(&PATH_STEAM.join("compatibilitytools.d"), None, &mut out);
+    scan_dir(
+        &PATH_STEAM.join("steamapps/common"),
+        Some("Proton"),
+        &mut out,
+    );
+    for dir in extra_dirs {
+        let dir = PathBuf::from(dir);
+        match read_build(&dir) {
+            Some(build) => out.push(build),
+            None => scan_dir(&dir, None, &mut out),
+        }
+    }
+
+    out.sort_by_key(|b| std::cmp::Reverse(version_key(&b.version)));
+    out.dedup_by(|a, b| a.path == b.path);
+    out
+}
+
+fn scan_dir(dir: &Path, prefix: Option<&str>, out: &mut Vec<ProtonBuild>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+        if prefix.is_some_and(|p| !name.starts_with(p)) {
+            continue;
+        }
+        if let Some(build) = read_build(&entry.path()) {
+            out.push(build);
+        }
+    }
+}
+
+fn read_build(path: &Path) -> Option<ProtonBuild> {
+    if !path.join("proton").is_file() {
+        return None;
+    }
+    let dirname = path.file_name()?.to_string_lossy().to_string();
+    let name = std::fs::read_to_string(path.join("compatibilitytool.vdf"))
+        .ok()
+        .and_then(|vdf| vdf_value(&vdf, "display_name"))
+        .unwrap_or(dirname.clone());
+    // The version file holds "<timestamp> <version>"
+    let version = std::fs::read_to_string(path.join("version"))
+        .ok()
+        .and_then(|v| v.split_whitespace().nth(1).map(|s| s.to_string()))
+        .unwrap_or(dirname);
+    Some(ProtonBuild {
+        name,
+        version,
+        path: path.to_path_buf(),
+    })
+}
+
+fn vdf_value(vdf: &str, key: &str) -> Option<String> {
+    let quoted = format!("\"{key}\"");
+    vdf.lines().find_map(|line| {
+        let rest = line.trim().strip_prefix(&quoted)?;
+        Some(rest.trim().trim_matches('"').to_string())
+    })
+}
+
+// Turns "GE-Proton9-20" into [9, 20] and "Proton 8.0-5" into [8, 0, 5] for comparisons.
+// Builds without numbers, like "Proton - Experimental", give an empty key.
+pub fn version_key(version: &str) -> Vec<u32> {
+    version
+        .split(|c: char| !c.is_ascii_digit())
+        .filter(|s| !s.is_empty())
+        .filter_map(|s| s.parse().ok())
+        .collect()
+}
+
+// Whether `version` is at least `min`. Unversioned builds are assumed to be recent enough.
+pub fn version_at_least(version: &str, min: &str) -> bool {
+    let key = version_key(version);
+    key.is_empty() || key >= version_key(min)
+}
+
+// Finds an installed build by path, directory name, display name or version
+pub fn find_proton_build<'a>(builds: &'a [ProtonBuild], query: &str) -> Option<&'a ProtonBuild> {
+    builds.iter().find(|b| {
+        b.path == Path::new(query)
+            || b.path.file_name().is_some_and(|n| n == query)
+            || b.name == query
+            || b.version == query
+    })
+}
@@ -0,0 +1,53 @@
+use std::path::PathBuf;
+
+use crate::paths::*;
+
+// An installed Steam Linux Runtime that native games can be run in
+#[derive(Clone)]
+pub struct SteamRuntime {
+    // Lowercase name handlers refer to it by, e.g. "scout", "soldier" or "sniper"
+    pub name: String,
+    pub entry_point: PathBuf,
+}
+
+// Finds the scout LD_LIBRARY_PATH runtime bundled with Steam, and every pressure-vessel
+// runtime (SteamLinuxRuntime_<name>) in any Steam library
+pub fn scan_runtimes() -> Vec<SteamRuntime> {
+    let mut out = Vec::new();
+
+    let scout = PATH_STEAM.join("ubuntu12_32/steam-runtime/run.sh");
+    if scout.exists() {
+        out.push(SteamRuntime {
+            name: "scout".to_string(),
+            entry_point: scout,
+        });
+    }
+
+    let libraries = steamlocate::SteamDir::locate()
+        .and_then(|steamdir| steamdir.library_paths())
+        .unwrap_or_else(|_| vec![PATH_STEAM.clone()]);
+    for library in libraries {
+        let Ok(entries) = std::fs::read_dir(library.join("steamapps/common")) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let dirname = entry.file_name().to_string_lossy().to_string();
+            let Some(name) = dirname.strip_prefix("SteamLinuxRuntime_") else {
+                continue;
+            };
+            let entry_point = entry.path().join("_v2-entry-point");
+            if !entry_point.exists() || out.iter().any(|r| r.name == name.to_lowercase()) {
+                continue;
+            }
+            out.push(SteamRuntime {
+                name: name.to_lowercase(),
+                entry_point,
+            });
+        }
+    }
+    out
+}
+
+pub fn find_runtime<'a>(runtimes: &'a [SteamRuntime], name: &str) -> Option<&'a SteamRuntime> {
+    runtimes.iter().find(|r| r.name == name.to_lowercase())
+}
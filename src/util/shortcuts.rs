@@ -0,0 +1,236 @@
+use std::error::Error;
+use std::path::{Path, PathBuf};
+
+use crate::paths::*;
+use crate::util::vdf::{VdfValue, read_binary_vdf, write_binary_vdf};
+
+// Tag marking the shortcuts PartyDeck manages, so user-made ones are never touched
+const SHORTCUT_TAG: &str = "PartyDeck";
+
+// A non-Steam game entry pointing at PartyDeck
+pub struct SteamShortcut {
+    pub name: String,
+    pub exe: PathBuf,
+    pub start_dir: PathBuf,
+    pub launch_options: String,
+    pub icon: Option<PathBuf>,
+    // Wide image used for the library capsule and hero banner
+    pub artwork: Option<PathBuf>,
+}
+
+// The config folder of the Steam user who logged in last
+fn steam_user_config() -> Result<PathBuf, Box<dyn Error>> {
+    let userdata = PATH_STEAM.join("userdata");
+    let mut newest: Option<(std::time::SystemTime, PathBuf)> = None;
+    for entry in std::fs::read_dir(&userdata)?.flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+        // "0" and "anonymous" aren't real accounts
+        if name == "0" || !name.chars().all(|c| c.is_ascii_digit()) {
+            continue;
+        }
+        let config = entry.path().join("config");
+        let Ok(modified) = config
+            .join("localconfig.vdf")
+            .metadata()
+            .and_then(|m| m.modified())
+        else {
+            continue;
+        };
+        if newest.as_ref().is_none_or(|(time, _)| modified > *time) {
+            newest = Some((modified, config));
+        }
+    }
+    newest
+        .map(|(_, config)| config)
+        .ok_or_else(|| format!("No Steam user found in {}", userdata.display()).into())
+}
+
+// Returns the file's root, to write back with save_shortcuts, and its shortcut entries
+fn load_shortcuts(path: &Path) -> Result<(VdfValue, Vec<VdfValue>), Box<dyn Error>> {
+    if !path.exists() {
+        return Ok((VdfValue::Map(Vec::new()), Vec::new()));
+    }
+    let root = read_binary_vdf(&std::fs::read(path)?)?;
+    let shortcuts = match root.get("shortcuts") {
+        Some(VdfValue::Map(entries)) => entries.iter().map(|(_, v)| v.clone()).collect(),
+        Some(_) => return Err("shortcuts.vdf is malformed".into()),
+        None => Vec::new(),
+    };
+    Ok((root, shortcuts))
+}
+
+// Replaces the root's shortcuts, keeping any other keys Steam stored next to them
+fn save_shortcuts(
+    path: &Path,
+    root: VdfValue,
+    shortcuts: Vec<VdfValue>,
+) -> Result<(), Box<dyn Error>> {
+    // Steam expects the entries to be numbered from 0
+    let entries = VdfValue::Map(
+        shortcuts
+            .into_iter()
+            .enumerate()
+            .map(|(i, v)| (i.to_string(), v))
+            .collect(),
+    );
+    let VdfValue::Map(mut keys) = root else {
+        return Err("shortcuts.vdf is malformed".into());
+    };
+    match keys
+        .iter_mut()
+        .find(|(key, _)| key.eq_ignore_ascii_case("shortcuts"))
+    {
+        Some((_, value)) => *value = entries,
+        None => keys.push(("shortcuts".to_string(), entries)),
+    }
+    let tmp = path.with_extension("vdf.partydeck");
+    std::fs::write(&tmp, write_binary_vdf(&VdfValue::Map(keys)))?;
+    std::fs::rename(tmp, path)?;
+    Ok(())
+}
+
+// Steam overwrites shortcuts.vdf when it exits, so changes made while it runs would be lost
+fn check_steam_closed() -> Result<(), Box<dyn Error>> {
+    match steam_running() {
+        true => Err(
+            "Steam is running. Close Steam first, as it overwrites its shortcuts when it exits."
+                .into(),
+        ),
+        false => Ok(()),
+    }
+}
+
+fn is_ours(shortcut: &VdfValue, name: &str) -> bool {
+    let tagged = match shortcut.get("tags") {
+        Some(VdfValue::Map(tags)) => tags.iter().any(|(_, t)| t.as_str() == Some(SHORTCUT_TAG)),
+        _ => false,
+    };
+    tagged && shortcut.get("AppName").and_then(|v| v.as_str()) == Some(name)
+}
+
+// Adds the shortcut, replacing a PartyDeck shortcut of the same name. Returns its app id.
+pub fn add_steam_shortcut(shortcut: &SteamShortcut) -> Result<u32, Box<dyn Error>> {
+    check_steam_closed()?;
+    let config = steam_user_config()?;
+    let path = config.join("shortcuts.vdf");
+    let (root, mut shortcuts) = load_shortcuts(&path)?;
+
+    let exe = format!("\"{}\"", shortcut.exe.display());
+    let appid = shortcut_appid(&exe, &shortcut.name);
+    let grid = config.join("grid");
+    let icon = match &shortcut.icon {
+        Some(src) => copy_artwork(src, &grid, &format!("{appid}_icon"))?,
+        None => String::new(),
+    };
+    if let Some(src) = &shortcut.artwork {
+        copy_artwork(src, &grid, &appid.to_string())?;
+        copy_artwork(src, &grid, &format!("{appid}_hero"))?;
+    }
+
+    let str = |s: &str| VdfValue::Str(s.to_string());
+    let entry = VdfValue::Map(vec![
+        ("appid".to_string(), VdfValue::Int(appid as i32)),
+        ("AppName".to_string(), str(&shortcut.name)),
+        ("Exe".to_string(), str(&exe)),
+        (
+            "StartDir".to_string(),
+            str(&format!("\"{}\"", shortcut.start_dir.display())),
+        ),
+        ("icon".to_string(), str(&icon)),
+        ("ShortcutPath".to_string(), str("")),
+        ("LaunchOptions".to_string(), str(&shortcut.launch_options)),
+        ("IsHidden".to_string(), VdfValue::Int(0)),
+        ("AllowDesktopConfig".to_string(), VdfValue::Int(1)),
+        ("AllowOverlay".to_string(), VdfValue::Int(1)),
+        ("OpenVR".to_string(), VdfValue::Int(0)),
+        ("Devkit".to_string(), VdfValue::Int(0)),
+        ("DevkitGameID".to_string(), str("")),
+        ("DevkitOverrideAppID".to_string(), VdfValue::Int(0)),
+        ("LastPlayTime".to_string(), VdfValue::Int(0)),
+        ("FlatpakAppID".to_string(), str("")),
+        (
+            "tags".to_string(),
+            VdfValue::Map(vec![("0".to_string(), str(SHORTCUT_TAG))]),
+        ),
+    ]);
+
+    match shortcuts.iter().position(|s| is_ours(s, &shortcut.name)) {
+        Some(i) => shortcuts[i] = entry,
+        None => shortcuts.push(entry),
+    }
+    save_shortcuts(&path, root, shortcuts)?;
+    Ok(appid)
+}
+
+// Removes the PartyDeck shortcut with this name and its artwork. Returns whether it existed.
+pub fn remove_steam_shortcut(name: &str) -> Result<bool, Box<dyn Error>> {
+    check_steam_closed()?;
+    let config = steam_user_config()?;
+    let path = config.join("shortcuts.vdf");
+    let (root, mut shortcuts) = load_shortcuts(&path)?;
+    let Some(i) = shortcuts.iter().position(|s| is_ours(s, name)) else {
+        return Ok(false);
+    };
+    let removed = shortcuts.remove(i);
+    save_shortcuts(&path, root, shortcuts)?;
+
+    if let Some(appid) = removed.get("appid").and_then(|v| v.as_int()) {
+        let appid = appid as u32;
+        let Ok(entries) = std::fs::read_dir(config.join("grid")) else {
+            return Ok(true);
+        };
+        for entry in entries.flatten() {
+            let file = entry.file_name().to_string_lossy().to_string();
+            let stem = file.split('.').next().unwrap_or_default();
+            if [
+                appid.to_string(),
+                format!("{appid}_hero"),
+                format!("{appid}_icon"),
+            ]
+            .contains(&stem.to_string())
+            {
+                let _ = std::fs::remove_file(entry.path());
+            }
+        }
+    }
+    Ok(true)
+}
+
+// Steam only reads shortcuts.vdf on startup and overwrites it on exit
+fn steam_running() -> bool {
+    let Ok(entries) = std::fs::read_dir("/proc") else {
+        return false;
+    };
+    entries.flatten().any(|entry| {
+        std::fs::read_to_string(entry.path().join("comm")).is_ok_and(|comm| comm.trim() == "steam")
+    })
+}
+
+// Copies an image into the grid folder as <name>.<ext>, returning the new path
+fn copy_artwork(src: &Path, grid: &Path, name: &str) -> Result<String, Box<dyn Error>> {
+    std::fs::create_dir_all(grid)?;
+    let ext = src.extension().unwrap_or_default().to_string_lossy();
+    let dest = grid.join(format!("{name}.{ext}"));
+    std::fs::copy(src, &dest)?;
+    Ok(dest.to_string_lossy().to_string())
+}
+
+// The id Steam derives for non-Steam games, which also names their grid artwork
+fn shortcut_appid(exe: &str, name: &str) -> u32 {
+    crc32(format!("{exe}{name}").as_bytes()) | 0x80000000
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = 0xffffffffu32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xedb88320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
@@ -86,7 +86,7 @@ pub fn get_instance_resolution(
 }
 
 // Sends the splitscreen script to the active KWin session through DBus
-pub fn kwin_dbus_start_script(file: PathBuf) -> Result<(), Box<dyn Error>> {
+pub fn kwin_dbus_start_script(file: PathBuf, name: &str) -> Result<(), Box<dyn Error>> {
     log_info(&format!("Loading script {}...", file.display()));
     if !file.exists() {
         log_error("Script file doesn't exist!");
@@ -101,7 +101,7 @@ pub fn kwin_dbus_start_script(file: PathBuf) -> Result<(), Box<dyn Error>> {
         "org.kde.kwin.Scripting",
     )?;
 
-    let _: i32 = proxy.call("loadScript", &(file.to_string_lossy(), "splitscreen"))?;
+    let _: i32 = proxy.call("loadScript", &(file.to_string_lossy(), name))?;
     log_info("Script loaded. Starting...");
     let _: () = proxy.call("start", &())?;
 
@@ -109,8 +109,8 @@ pub fn kwin_dbus_start_script(file: PathBuf) -> Result<(), Box<dyn Error>> {
     Ok(())
 }
 
-pub fn kwin_dbus_unload_script() -> Result<(), Box<dyn Error>> {
-    log_info("Unloading splitscreen script...");
+pub fn kwin_dbus_unload_script(name: &str) -> Result<(), Box<dyn Error>> {
+    log_info(&format!("Unloading script {name}..."));
     let conn = zbus::blocking::Connection::session()?;
     let proxy = zbus::blocking::Proxy::new(
         &conn,
@@ -119,7 +119,7 @@ pub fn kwin_dbus_unload_script() -> Result<(), Box<dyn Error>> {
         "org.kde.kwin.Scripting",
     )?;
 
-    let _: bool = proxy.call("unloadScript", &("splitscreen"))?;
+    let _: bool = proxy.call("unloadScript", &(name))?;
 
     log_info("Script unloaded.");
     Ok(())
@@ -0,0 +1,141 @@
+use std::error::Error;
+
+// Steam's binary KeyValues format, as used by shortcuts.vdf.
+// Every value starts with a type byte and a NUL-terminated key; maps end with END.
+const MAP: u8 = 0x00;
+const STRING: u8 = 0x01;
+const INT: u8 = 0x02;
+const FLOAT: u8 = 0x03;
+const UINT64: u8 = 0x07;
+const END: u8 = 0x08;
+const INT64: u8 = 0x0a;
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum VdfValue {
+    Map(Vec<(String, VdfValue)>),
+    Str(String),
+    Int(i32),
+    Float(f32),
+    UInt64(u64),
+    Int64(i64),
+}
+
+impl VdfValue {
+    pub fn get(&self, key: &str) -> Option<&VdfValue> {
+        match self {
+            VdfValue::Map(entries) => entries
+                .iter()
+                .find(|(k, _)| k.eq_ignore_ascii_case(key))
+                .map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            VdfValue::Str(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_int(&self) -> Option<i32> {
+        match self {
+            VdfValue::Int(i) => Some(*i),
+            _ => None,
+        }
+    }
+}
+
+// Parses a whole file into its top-level map. An empty file is an empty map.
+pub fn read_binary_vdf(data: &[u8]) -> Result<VdfValue, Box<dyn Error>> {
+    let mut pos = 0;
+    let root = read_map(data, &mut pos, true)?;
+    Ok(root)
+}
+
+pub fn write_binary_vdf(root: &VdfValue) -> Vec<u8> {
+    let mut out = Vec::new();
+    if let VdfValue::Map(entries) = root {
+        write_entries(&mut out, entries);
+    }
+    out.push(END);
+    out
+}
+
+fn read_map(data: &[u8], pos: &mut usize, top_level: bool) -> Result<VdfValue, Box<dyn Error>> {
+    let mut entries = Vec::new();
+    loop {
+        let Some(&kind) = data.get(*pos) else {
+            // Only the top level may end with the file
+            if top_level {
+                return Ok(VdfValue::Map(entries));
+            }
+            return Err("Unexpected end of VDF data".into());
+        };
+        *pos += 1;
+        if kind == END {
+            return Ok(VdfValue::Map(entries));
+        }
+        let key = read_cstr(data, pos)?;
+        let value = match kind {
+            MAP => read_map(data, pos, false)?,
+            STRING => VdfValue::Str(read_cstr(data, pos)?),
+            INT => VdfValue::Int(i32::from_le_bytes(read_bytes(data, pos)?)),
+            FLOAT => VdfValue::Float(f32::from_le_bytes(read_bytes(data, pos)?)),
+            UINT64 => VdfValue::UInt64(u64::from_le_bytes(read_bytes(data, pos)?)),
+            INT64 => VdfValue::Int64(i64::from_le_bytes(read_bytes(data, pos)?)),
+            other => return Err(format!("Unsupported VDF value type {other:#04x}").into()),
+        };
+        entries.push((key, value));
+    }
+}
+
+fn read_cstr(data: &[u8], pos: &mut usize) -> Result<String, Box<dyn Error>> {
+    let len = data[*pos..]
+        .iter()
+        .position(|&b| b == 0)
+        .ok_or("Unterminated string in VDF data")?;
+    // Refuse non-UTF-8 rather than rewrite someone's shortcuts lossily
+    let s = String::from_utf8(data[*pos..*pos + len].to_vec())?;
+    *pos += len + 1;
+    Ok(s)
+}
+
+fn read_bytes<const N: usize>(data: &[u8], pos: &mut usize) -> Result<[u8; N], Box<dyn Error>> {
+    let bytes = data
+        .get(*pos..*pos + N)
+        .ok_or("Unexpected end of VDF data")?;
+    *pos += N;
+    Ok(bytes.try_into()?)
+}
+
+fn write_entries(out: &mut Vec<u8>, entries: &[(String, VdfValue)]) {
+    for (key, value) in entries {
+        let kind = match value {
+            VdfValue::Map(_) => MAP,
+            VdfValue::Str(_) => STRING,
+            VdfValue::Int(_) => INT,
+            VdfValue::Float(_) => FLOAT,
+            VdfValue::UInt64(_) => UINT64,
+            VdfValue::Int64(_) => INT64,
+        };
+        out.push(kind);
+        write_cstr(out, key);
+        match value {
+            VdfValue::Map(children) => {
+                write_entries(out, children);
+                out.push(END);
+            }
+            VdfValue::Str(s) => write_cstr(out, s),
+            VdfValue::Int(i) => out.extend(i.to_le_bytes()),
+            VdfValue::Float(f) => out.extend(f.to_le_bytes()),
+            VdfValue::UInt64(u) => out.extend(u.to_le_bytes()),
+            VdfValue::Int64(i) => out.extend(i.to_le_bytes()),
+        }
+    }
+}
+
+fn write_cstr(out: &mut Vec<u8>, s: &str) {
+    out.extend(s.as_bytes());
+    out.push(0);
+}